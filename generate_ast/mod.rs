@@ -18,16 +18,36 @@ pub fn generate_ast(output_dir: &str) -> io::Result<()> {
             "use crate::error::*;",
             "use crate::token::*;",
             "use crate::lit::*;",
+            "use crate::stmt::*;",
+            "use crate::pattern::*;",
+            "use crate::signature::*;",
+            "use std::cell::Cell;",
         ],
         &[
-            "Assign   : Token name, Box<Expr> value",
+            "Assign   : Token name, Box<Expr> value, Cell<Option<usize>> depth",
             "Binary   : Box<Expr> left, Token operator, Box<Expr> right",
-            "Call     : Box<Expr> callee, Token paren, Vec<Expr> arguments",
+            "Call     : Box<Expr> callee, Token paren, Vec<Argument> arguments",
+            "Chain    : Box<Expr> expression",
+            "Compound : Box<Expr> target, Token operator, Box<Expr> value, bool postfix",
+            "Conditional : Box<Expr> condition, Box<Expr> then_branch, Box<Expr> else_branch",
+            "Destructure : Pattern pattern, Token equals, Box<Expr> value, Vec<Cell<Option<usize>>> depths",
+            "Get      : Box<Expr> object, Token name",
             "Grouping : Box<Expr> expression",
+            "Index    : Box<Expr> object, Token bracket, Box<Expr> index",
+            "IndexSet : Box<Expr> object, Token bracket, Box<Expr> index, Box<Expr> value",
+            "List     : Vec<Expr> elements",
+            "Map      : Token brace, Vec<Expr> keys, Vec<Expr> values",
+            "OptionalCall : Box<Expr> callee, Token paren, Vec<Argument> arguments",
+            "OptionalGet : Box<Expr> object, Token name",
+            "Interpolation : Vec<Expr> parts",
+            "Lambda   : FunctionStmt declaration",
             "Literal  : Option<Lit> value",
             "Logical   : Box<Expr> left, Token operator, Box<Expr> right",
+            "Set      : Box<Expr> object, Token name, Box<Expr> value",
+            "Super    : Token keyword, Token method, Cell<Option<usize>> depth",
+            "This     : Token keyword, Cell<Option<usize>> depth",
             "Unary    : Token operator, Box<Expr> right",
-            "Variable : Token name",
+            "Variable : Token name, Cell<Option<usize>> depth",
         ],
     )?;
     define_ast(
@@ -38,15 +58,33 @@ pub fn generate_ast(output_dir: &str) -> io::Result<()> {
             "use crate::token::*;",
             // "use crate::lit::*;",
             "use crate::expr::*;",
+            "use crate::lox_enum::*;",
+            "use crate::pattern::*;",
+            "use crate::signature::*;",
+            "use std::rc::Rc;",
         ],
         &[
             "Block        : Vec<Stmt> statements",
+            "Break        : Token keyword",
+            "Class        : Token name, Option<VariableExpr> superclass, Vec<VariableExpr> traits, Vec<FunctionStmt> methods, Vec<FunctionStmt> getters, Vec<FunctionStmt> setters, Vec<FunctionStmt> statics",
+            "Const        : Token name, Expr initializer",
+            "Continue     : Token keyword",
+            "Destructure  : Pattern pattern, Token equals, Expr initializer",
+            "Enum         : Token name, Vec<Variant> variants",
             "Expression   : Expr expression",
-            "Function     : Token name, Vec<Token> params, Vec<Stmt> body",
+            "ForIn        : Token name, Expr iterable, Box<Stmt> body",
+            "Function     : Token name, Rc<Vec<Parameter>> params, Option<Token> rest, Rc<Vec<Stmt>> body",
+            "Import       : Token keyword, Token path, Option<Token> alias, Vec<Token> names",
             "If           : Expr condition, Box<Stmt> then_branch, Option<Box<Stmt>> else_branch",
+            "Match        : Token keyword, Expr subject, Vec<MatchArm> arms",
             "Print        : Expr expression",
+            "Return       : Token keyword, Option<Expr> value",
+            "Throw        : Token keyword, Expr value",
+            "Trait        : Token name, Vec<FunctionStmt> methods",
+            "Try          : Vec<Stmt> body, Option<Token> catch_name, Option<Vec<Stmt>> catch_body, Option<Vec<Stmt>> finally_body",
             "Var          : Token name, Option<Expr> initializer",
-            "While        : Expr condition, Box<Stmt> body",
+            "Yield        : Token keyword, Option<Expr> value",
+            "While        : Expr condition, Box<Stmt> body, Option<Expr> increment",
         ],
     )
 }
@@ -1,13 +1,15 @@
 use crate::{error::LoxResult, lit::Lit, token::Token};
 use std::{
     cell::RefCell,
-    collections::{hash_map::Entry, HashMap},
+    collections::{HashMap, HashSet},
     rc::Rc,
 };
 
 #[derive(Debug)]
 pub struct Environment {
     values: HashMap<String, Lit>,
+    /// Names in `values` that were declared with `const` and can't be assigned to
+    constants: HashSet<String>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -21,6 +23,7 @@ impl Environment {
     pub fn new() -> Self {
         Self {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
         }
     }
@@ -28,14 +31,47 @@ impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Self {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
 
-    pub fn define(&mut self, name: &str, value: Lit) {
+    /// Defines a variable declared by a statement, which can't take the name of a constant in the
+    /// same scope. The resolver already rejects that, except for globals declared by an earlier run.
+    pub fn define(&mut self, name: &Token, value: Lit) -> Result<(), LoxResult> {
+        if self.constants.contains(&name.lexeme) {
+            return Err(LoxResult::runtime_error(
+                name.clone(),
+                &format!("Can't redeclare constant '{}'.", name.lexeme),
+            ));
+        }
+        self.bind(&name.lexeme, value);
+        Ok(())
+    }
+
+    pub fn define_const(&mut self, name: &Token, value: Lit) -> Result<(), LoxResult> {
+        self.define(name, value)?;
+        self.constants.insert(name.lexeme.clone());
+        Ok(())
+    }
+
+    /// Binds a name in a scope that the interpreter set up itself, like parameters or `this`,
+    /// where there are no constants in the way
+    pub fn bind(&mut self, name: &str, value: Lit) {
         self.values.insert(name.to_string(), value);
     }
 
+    fn check_mutable(&self, name: &Token) -> Result<(), LoxResult> {
+        if self.constants.contains(&name.lexeme) {
+            Err(LoxResult::runtime_error(
+                name.clone(),
+                &format!("Can't assign to constant '{}'.", name.lexeme),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn get(&self, name: &Token) -> Result<Lit, LoxResult> {
         if let Some(lit) = self.values.get(&name.lexeme) {
             return Ok(lit.clone());
@@ -49,9 +85,15 @@ impl Environment {
         ))
     }
 
+    /// Looks a variable up in this scope only, without falling back to the enclosing ones
+    pub fn get_own(&self, name: &str) -> Option<Lit> {
+        self.values.get(name).cloned()
+    }
+
     pub fn assign(&mut self, name: &Token, value: Lit) -> Result<(), LoxResult> {
-        if let Entry::Occupied(mut ent) = self.values.entry(name.lexeme.clone()) {
-            ent.insert(value);
+        if self.values.contains_key(&name.lexeme) {
+            self.check_mutable(name)?;
+            self.values.insert(name.lexeme.clone(), value);
             Ok(())
         } else if let Some(enclosing) = &self.enclosing {
             enclosing.borrow_mut().assign(name, value)
@@ -62,4 +104,44 @@ impl Environment {
             ))
         }
     }
+
+    /// Gets a variable from exactly `distance` scopes up the chain, as recorded by the resolver
+    pub fn get_at(&self, distance: usize, name: &Token) -> Result<Lit, LoxResult> {
+        if distance == 0 {
+            self.values.get(&name.lexeme).cloned().ok_or_else(|| {
+                LoxResult::runtime_error(
+                    name.clone(),
+                    &format!("Undefined variable '{}'.", name.lexeme),
+                )
+            })
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get_at(distance - 1, name)
+        } else {
+            Err(LoxResult::system_error(&format!(
+                "Resolved scope for '{}' is out of range.",
+                name.lexeme
+            )))
+        }
+    }
+
+    /// Assigns to a variable exactly `distance` scopes up the chain, as recorded by the resolver
+    pub fn assign_at(
+        &mut self,
+        distance: usize,
+        name: &Token,
+        value: Lit,
+    ) -> Result<(), LoxResult> {
+        if distance == 0 {
+            self.check_mutable(name)?;
+            self.values.insert(name.lexeme.clone(), value);
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign_at(distance - 1, name, value)
+        } else {
+            Err(LoxResult::system_error(&format!(
+                "Resolved scope for '{}' is out of range.",
+                name.lexeme
+            )))
+        }
+    }
 }
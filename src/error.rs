@@ -1,11 +1,39 @@
-use crate::{token::Token, token_type::TokenType};
+use crate::{lit::Lit, token::Token, token_type::TokenType};
 
 #[derive(Debug)]
 pub enum LoxResult {
-    ParseError { token: Token, message: String },
-    RuntimeError { token: Token, message: String },
-    Error { line: usize, message: String },
-    SystemError { message: String },
+    ParseError {
+        token: Token,
+        message: String,
+    },
+    RuntimeError {
+        token: Token,
+        message: String,
+    },
+    Error {
+        line: usize,
+        message: String,
+    },
+    ScanError {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    SystemError {
+        message: String,
+    },
+    Throw {
+        token: Token,
+        value: Lit,
+    },
+    ReturnValue {
+        value: Lit,
+    },
+    /// Like `ReturnValue`, these only unwind the interpreter back to the enclosing loop
+    Break,
+    Continue,
+    /// Unwinds an optional chain whose receiver was nil, back to where the chain began
+    ShortCircuit,
 }
 
 impl LoxResult {
@@ -18,6 +46,16 @@ impl LoxResult {
         error
     }
 
+    pub fn scan_error(line: usize, column: usize, message: &str) -> LoxResult {
+        let err = LoxResult::ScanError {
+            line,
+            column,
+            message: message.to_string(),
+        };
+        err.report("");
+        err
+    }
+
     pub fn parse_error(token: Token, message: &str) -> LoxResult {
         let err = LoxResult::ParseError {
             token,
@@ -27,13 +65,13 @@ impl LoxResult {
         err
     }
 
+    /// Runtime errors can still be caught by a `try` statement, so the interpreter reports them
+    /// once they have escaped every statement instead
     pub fn runtime_error(token: Token, message: &str) -> LoxResult {
-        let err = LoxResult::RuntimeError {
+        LoxResult::RuntimeError {
             token,
             message: message.to_string(),
-        };
-        err.report("");
-        err
+        }
     }
 
     pub fn system_error(message: &str) -> LoxResult {
@@ -44,11 +82,32 @@ impl LoxResult {
         err
     }
 
+    /// Not an actual error, it unwinds the interpreter back to the function call that is returning,
+    /// so unlike the other constructors it is never reported
+    pub fn return_value(value: Lit) -> LoxResult {
+        LoxResult::ReturnValue { value }
+    }
+
+    /// A value thrown by a `throw` statement, reported like a runtime error if it is never caught
+    pub fn throw(token: Token, value: Lit) -> LoxResult {
+        LoxResult::Throw { token, value }
+    }
+
     pub fn report(&self, loc: &str) {
         match self {
             LoxResult::Error { line, message } => {
                 eprintln!("[line: {}] Error{}: {}", line, loc, message);
             }
+            LoxResult::ScanError {
+                line,
+                column,
+                message,
+            } => {
+                eprintln!(
+                    "[line: {}, column: {}] Error{}: {}",
+                    line, column, loc, message
+                );
+            }
             LoxResult::ParseError { token, message }
             | LoxResult::RuntimeError { token, message } => {
                 if token.is(TokenType::Eof) {
@@ -57,9 +116,23 @@ impl LoxResult {
                     eprintln!("{} at '{}' {}", token.line, token, message);
                 }
             }
+            LoxResult::Throw { token, value } => {
+                // A rethrown runtime error is reported where it first happened
+                if let Lit::Instance(instance) = value {
+                    if let Some(origin) = &instance.origin {
+                        origin.report(loc);
+                        return;
+                    }
+                }
+                eprintln!("{} at '{}' Uncaught {}", token.line, token, value);
+            }
             LoxResult::SystemError { message } => {
                 eprintln!("System Error: {}", message);
             }
+            LoxResult::ReturnValue { .. }
+            | LoxResult::Break
+            | LoxResult::Continue
+            | LoxResult::ShortCircuit => {}
         }
     }
 }
@@ -1,14 +1,34 @@
 use crate::error::*;
 use crate::token::*;
 use crate::lit::*;
+use crate::stmt::*;
+use crate::pattern::*;
+use crate::signature::*;
+use std::cell::Cell;
 
 pub enum Expr {
     Assign(AssignExpr),
     Binary(BinaryExpr),
     Call(CallExpr),
+    Chain(ChainExpr),
+    Compound(CompoundExpr),
+    Conditional(ConditionalExpr),
+    Destructure(DestructureExpr),
+    Get(GetExpr),
     Grouping(GroupingExpr),
+    Index(IndexExpr),
+    IndexSet(IndexSetExpr),
+    List(ListExpr),
+    Map(MapExpr),
+    OptionalCall(OptionalCallExpr),
+    OptionalGet(OptionalGetExpr),
+    Interpolation(InterpolationExpr),
+    Lambda(LambdaExpr),
     Literal(LiteralExpr),
     Logical(LogicalExpr),
+    Set(SetExpr),
+    Super(SuperExpr),
+    This(ThisExpr),
     Unary(UnaryExpr),
     Variable(VariableExpr),
 }
@@ -25,15 +45,63 @@ impl Expr {
             Expr::Call(expr) => {
                 expr.accept(visitor)
             }
+            Expr::Chain(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Compound(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Conditional(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Destructure(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Get(expr) => {
+                expr.accept(visitor)
+            }
             Expr::Grouping(expr) => {
                 expr.accept(visitor)
             }
+            Expr::Index(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::IndexSet(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::List(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Map(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::OptionalCall(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::OptionalGet(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Interpolation(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Lambda(expr) => {
+                expr.accept(visitor)
+            }
             Expr::Literal(expr) => {
                 expr.accept(visitor)
             }
             Expr::Logical(expr) => {
                 expr.accept(visitor)
             }
+            Expr::Set(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::Super(expr) => {
+                expr.accept(visitor)
+            }
+            Expr::This(expr) => {
+                expr.accept(visitor)
+            }
             Expr::Unary(expr) => {
                 expr.accept(visitor)
             }
@@ -47,6 +115,7 @@ impl Expr {
 pub struct AssignExpr {
     pub name: Token,
     pub value: Box<Expr>,
+    pub depth: Cell<Option<usize>>,
 }
 
 pub struct BinaryExpr {
@@ -58,13 +127,84 @@ pub struct BinaryExpr {
 pub struct CallExpr {
     pub callee: Box<Expr>,
     pub paren: Token,
-    pub arguments: Vec<Expr>,
+    pub arguments: Vec<Argument>,
+}
+
+pub struct ChainExpr {
+    pub expression: Box<Expr>,
+}
+
+pub struct CompoundExpr {
+    pub target: Box<Expr>,
+    pub operator: Token,
+    pub value: Box<Expr>,
+    pub postfix: bool,
+}
+
+pub struct ConditionalExpr {
+    pub condition: Box<Expr>,
+    pub then_branch: Box<Expr>,
+    pub else_branch: Box<Expr>,
+}
+
+pub struct DestructureExpr {
+    pub pattern: Pattern,
+    pub equals: Token,
+    pub value: Box<Expr>,
+    pub depths: Vec<Cell<Option<usize>>>,
+}
+
+pub struct GetExpr {
+    pub object: Box<Expr>,
+    pub name: Token,
 }
 
 pub struct GroupingExpr {
     pub expression: Box<Expr>,
 }
 
+pub struct IndexExpr {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+}
+
+pub struct IndexSetExpr {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+}
+
+pub struct ListExpr {
+    pub elements: Vec<Expr>,
+}
+
+pub struct MapExpr {
+    pub brace: Token,
+    pub keys: Vec<Expr>,
+    pub values: Vec<Expr>,
+}
+
+pub struct OptionalCallExpr {
+    pub callee: Box<Expr>,
+    pub paren: Token,
+    pub arguments: Vec<Argument>,
+}
+
+pub struct OptionalGetExpr {
+    pub object: Box<Expr>,
+    pub name: Token,
+}
+
+pub struct InterpolationExpr {
+    pub parts: Vec<Expr>,
+}
+
+pub struct LambdaExpr {
+    pub declaration: FunctionStmt,
+}
+
 pub struct LiteralExpr {
     pub value: Option<Lit>,
 }
@@ -75,6 +215,23 @@ pub struct LogicalExpr {
     pub right: Box<Expr>,
 }
 
+pub struct SetExpr {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub value: Box<Expr>,
+}
+
+pub struct SuperExpr {
+    pub keyword: Token,
+    pub method: Token,
+    pub depth: Cell<Option<usize>>,
+}
+
+pub struct ThisExpr {
+    pub keyword: Token,
+    pub depth: Cell<Option<usize>>,
+}
+
 pub struct UnaryExpr {
     pub operator: Token,
     pub right: Box<Expr>,
@@ -82,15 +239,32 @@ pub struct UnaryExpr {
 
 pub struct VariableExpr {
     pub name: Token,
+    pub depth: Cell<Option<usize>>,
 }
 
 pub trait ExprVisitor<T> {
     fn visit_assign_expr(&mut self, expr: &AssignExpr) -> Result<T, LoxResult>;
     fn visit_binary_expr(&mut self, expr: &BinaryExpr) -> Result<T, LoxResult>;
     fn visit_call_expr(&mut self, expr: &CallExpr) -> Result<T, LoxResult>;
+    fn visit_chain_expr(&mut self, expr: &ChainExpr) -> Result<T, LoxResult>;
+    fn visit_compound_expr(&mut self, expr: &CompoundExpr) -> Result<T, LoxResult>;
+    fn visit_conditional_expr(&mut self, expr: &ConditionalExpr) -> Result<T, LoxResult>;
+    fn visit_destructure_expr(&mut self, expr: &DestructureExpr) -> Result<T, LoxResult>;
+    fn visit_get_expr(&mut self, expr: &GetExpr) -> Result<T, LoxResult>;
     fn visit_grouping_expr(&mut self, expr: &GroupingExpr) -> Result<T, LoxResult>;
+    fn visit_index_expr(&mut self, expr: &IndexExpr) -> Result<T, LoxResult>;
+    fn visit_indexset_expr(&mut self, expr: &IndexSetExpr) -> Result<T, LoxResult>;
+    fn visit_list_expr(&mut self, expr: &ListExpr) -> Result<T, LoxResult>;
+    fn visit_map_expr(&mut self, expr: &MapExpr) -> Result<T, LoxResult>;
+    fn visit_optionalcall_expr(&mut self, expr: &OptionalCallExpr) -> Result<T, LoxResult>;
+    fn visit_optionalget_expr(&mut self, expr: &OptionalGetExpr) -> Result<T, LoxResult>;
+    fn visit_interpolation_expr(&mut self, expr: &InterpolationExpr) -> Result<T, LoxResult>;
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr) -> Result<T, LoxResult>;
     fn visit_literal_expr(&mut self, expr: &LiteralExpr) -> Result<T, LoxResult>;
     fn visit_logical_expr(&mut self, expr: &LogicalExpr) -> Result<T, LoxResult>;
+    fn visit_set_expr(&mut self, expr: &SetExpr) -> Result<T, LoxResult>;
+    fn visit_super_expr(&mut self, expr: &SuperExpr) -> Result<T, LoxResult>;
+    fn visit_this_expr(&mut self, expr: &ThisExpr) -> Result<T, LoxResult>;
     fn visit_unary_expr(&mut self, expr: &UnaryExpr) -> Result<T, LoxResult>;
     fn visit_variable_expr(&mut self, expr: &VariableExpr) -> Result<T, LoxResult>;
 }
@@ -113,12 +287,90 @@ impl CallExpr {
     }
 }
 
+impl ChainExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_chain_expr(self)
+    }
+}
+
+impl CompoundExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_compound_expr(self)
+    }
+}
+
+impl ConditionalExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_conditional_expr(self)
+    }
+}
+
+impl DestructureExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_destructure_expr(self)
+    }
+}
+
+impl GetExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_get_expr(self)
+    }
+}
+
 impl GroupingExpr {
     pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_grouping_expr(self)
     }
 }
 
+impl IndexExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_index_expr(self)
+    }
+}
+
+impl IndexSetExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_indexset_expr(self)
+    }
+}
+
+impl ListExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_list_expr(self)
+    }
+}
+
+impl MapExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_map_expr(self)
+    }
+}
+
+impl OptionalCallExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_optionalcall_expr(self)
+    }
+}
+
+impl OptionalGetExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_optionalget_expr(self)
+    }
+}
+
+impl InterpolationExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_interpolation_expr(self)
+    }
+}
+
+impl LambdaExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_lambda_expr(self)
+    }
+}
+
 impl LiteralExpr {
     pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_literal_expr(self)
@@ -131,6 +383,24 @@ impl LogicalExpr {
     }
 }
 
+impl SetExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_set_expr(self)
+    }
+}
+
+impl SuperExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_super_expr(self)
+    }
+}
+
+impl ThisExpr {
+    pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_this_expr(self)
+    }
+}
+
 impl UnaryExpr {
     pub fn accept<T>(&self, visitor: &mut dyn ExprVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_unary_expr(self)
@@ -1,5 +1,7 @@
-use std::cell::Ref;
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::environment::Environment;
@@ -7,14 +9,36 @@ use crate::error::LoxResult;
 use crate::expr::*;
 use crate::lit::*;
 use crate::lox_callable::LoxCallable;
-use crate::lox_native::LoxNative;
-use crate::lox_native::NativeClock;
+use crate::lox_class::{LoxClass, Members};
+use crate::lox_enum::LoxEnum;
+use crate::lox_function::LoxFunction;
+use crate::lox_generator::GeneratorNext;
+use crate::lox_instance::LoxInstance;
+use crate::lox_iterator::LoxIterator;
+use crate::lox_map::MapKey;
+use crate::lox_module::LoxModule;
+use crate::lox_native::*;
+use crate::lox_trait::LoxTrait;
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
+use crate::signature::Argument;
 use crate::stmt::*;
+use crate::token::Token;
 use crate::token_type::TokenType;
 
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
+    /// Natives and other builtins, enclosing the global scope of every module
+    builtins: Rc<RefCell<Environment>>,
+    /// The class of the values that caught runtime errors are turned into
+    error_class: Rc<LoxClass>,
+    /// Imported modules by canonical path. A module maps to `None` while it is still running, so
+    /// that importing it again is caught as a cycle
+    modules: HashMap<PathBuf, Option<Rc<LoxModule>>>,
+    /// The directory of the file being run, which imports are relative to
+    directory: PathBuf,
 }
 
 impl Default for Interpreter {
@@ -25,15 +49,187 @@ impl Default for Interpreter {
 
 impl StmtVisitor<()> for Interpreter {
     fn visit_function_stmt(&mut self, stmt: &FunctionStmt) -> Result<(), LoxResult> {
+        let function = LoxFunction::new(
+            stmt,
+            Rc::clone(&self.environment),
+            Rc::clone(&self.globals),
+            false,
+        );
+        self.environment
+            .borrow_mut()
+            .define(&stmt.name, Lit::Func(Rc::new(function)))
+    }
+    fn visit_class_stmt(&mut self, stmt: &ClassStmt) -> Result<(), LoxResult> {
+        let superclass = if let Some(superclass) = &stmt.superclass {
+            match self.visit_variable_expr(superclass)? {
+                Lit::Class(class) => Some(class),
+                _ => {
+                    return Err(LoxResult::runtime_error(
+                        superclass.name.clone(),
+                        "Superclass must be a class.",
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut traits = Vec::new();
+        for name in &stmt.traits {
+            match self.visit_variable_expr(name)? {
+                Lit::Trait(lox_trait) => traits.push(lox_trait),
+                _ => {
+                    return Err(LoxResult::runtime_error(
+                        name.name.clone(),
+                        "Can only mix in traits.",
+                    ))
+                }
+            }
+        }
+
+        // Trait methods are copied in first so that the class's own methods replace them. Two
+        // traits with the same method conflict unless the class settles it with its own.
+        let mut methods: HashMap<String, Rc<LoxFunction>> = HashMap::new();
+        let mut origins: HashMap<&str, &str> = HashMap::new();
+        for lox_trait in &traits {
+            for (name, method) in &lox_trait.methods {
+                if stmt.methods.iter().any(|m| m.name.lexeme == *name) {
+                    continue;
+                }
+                if let Some(other) = origins.insert(name, &lox_trait.name) {
+                    return Err(LoxResult::runtime_error(
+                        stmt.name.clone(),
+                        &format!(
+                            "Traits '{}' and '{}' both define a method '{}'.",
+                            other, lox_trait.name, name
+                        ),
+                    ));
+                }
+                methods.insert(name.clone(), Rc::clone(method));
+            }
+        }
+
+        self.environment.borrow_mut().define(&stmt.name, Lit::Nil)?;
+
+        // Methods of a subclass close over an environment where `super` is defined
+        let enclosing = Rc::clone(&self.environment);
+        if let Some(superclass) = &superclass {
+            let mut environment = Environment::new_with_enclosing(Rc::clone(&enclosing));
+            environment.bind("super", Lit::Class(Rc::clone(superclass)));
+            self.environment = Rc::new(RefCell::new(environment));
+        }
+
+        for method in &stmt.methods {
+            let function = LoxFunction::new(
+                method,
+                Rc::clone(&self.environment),
+                Rc::clone(&self.globals),
+                method.name.lexeme == "init",
+            );
+            methods.insert(method.name.lexeme.clone(), Rc::new(function));
+        }
+
+        let accessors = |functions: &[FunctionStmt], environment: &Rc<RefCell<Environment>>| {
+            functions
+                .iter()
+                .map(|function| {
+                    let name = function.name.lexeme.clone();
+                    let function = LoxFunction::new(
+                        function,
+                        Rc::clone(environment),
+                        Rc::clone(&self.globals),
+                        false,
+                    );
+                    (name, Rc::new(function))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let members = Members {
+            methods,
+            getters: accessors(&stmt.getters, &self.environment),
+            setters: accessors(&stmt.setters, &self.environment),
+            // Static methods can't use `super` either, so they skip its scope
+            statics: accessors(&stmt.statics, &enclosing),
+        };
+
+        let class = LoxClass::new(&stmt.name.lexeme, superclass, members);
+        self.environment = enclosing;
+        self.environment
+            .borrow_mut()
+            .assign(&stmt.name, Lit::Class(class))?;
         Ok(())
     }
+
+    fn visit_trait_stmt(&mut self, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let methods = stmt
+            .methods
+            .iter()
+            .map(|method| {
+                let function = LoxFunction::new(
+                    method,
+                    Rc::clone(&self.environment),
+                    Rc::clone(&self.globals),
+                    method.name.lexeme == "init",
+                );
+                (method.name.lexeme.clone(), Rc::new(function))
+            })
+            .collect();
+        let lox_trait = LoxTrait {
+            name: stmt.name.lexeme.clone(),
+            methods,
+        };
+        self.environment
+            .borrow_mut()
+            .define(&stmt.name, Lit::Trait(Rc::new(lox_trait)))
+    }
+
     fn visit_while_stmt(&mut self, stmt: &WhileStmt) -> Result<(), LoxResult> {
         while self.evaluate(&stmt.condition)?.is_truthy() {
-            self.execute(&stmt.body)?;
+            match self.execute(&stmt.body) {
+                Err(LoxResult::Break) => break,
+                Err(LoxResult::Continue) | Ok(()) => {}
+                Err(e) => return Err(e),
+            }
+
+            if let Some(increment) = &stmt.increment {
+                self.evaluate(increment)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_forin_stmt(&mut self, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        let iterable = self.evaluate(&stmt.iterable)?;
+        let mut iterator = LoxIterator::new(self, iterable, &stmt.name)?;
+        while let Some(value) = iterator.next(self, &stmt.name)? {
+            if !self.execute_loop_body(stmt, value)? {
+                break;
+            }
         }
         Ok(())
     }
 
+    fn visit_match_stmt(&mut self, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        let (arm, environment) = self.match_arm(stmt, &Rc::clone(&self.environment))?;
+        self.execute_in(&stmt.arms[arm].body, environment)
+    }
+
+    fn visit_yield_stmt(&mut self, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        // Generators run their yields themselves, so this one isn't inside of a generator
+        Err(LoxResult::runtime_error(
+            stmt.keyword.clone(),
+            "Can only yield inside a generator.",
+        ))
+    }
+
+    fn visit_break_stmt(&mut self, _stmt: &BreakStmt) -> Result<(), LoxResult> {
+        Err(LoxResult::Break)
+    }
+
+    fn visit_continue_stmt(&mut self, _stmt: &ContinueStmt) -> Result<(), LoxResult> {
+        Err(LoxResult::Continue)
+    }
+
     fn visit_if_stmt(&mut self, stmt: &IfStmt) -> Result<(), LoxResult> {
         if self.evaluate(&stmt.condition)?.is_truthy() {
             self.execute(&stmt.then_branch)?;
@@ -57,6 +253,79 @@ impl StmtVisitor<()> for Interpreter {
         println!("{}", value);
         Ok(())
     }
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) -> Result<(), LoxResult> {
+        let value = if let Some(value) = &stmt.value {
+            self.evaluate(value)?
+        } else {
+            Lit::Nil
+        };
+        Err(LoxResult::return_value(value))
+    }
+    fn visit_const_stmt(&mut self, stmt: &ConstStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(&stmt.initializer)?;
+        self.environment
+            .borrow_mut()
+            .define_const(&stmt.name, value)
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        let module = self.import(&stmt.path)?;
+        for name in &stmt.names {
+            let value = module.get(name)?;
+            self.environment.borrow_mut().define(name, value)?;
+        }
+        if let Some(alias) = &stmt.alias {
+            self.environment
+                .borrow_mut()
+                .define(alias, Lit::Module(module))?;
+        }
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &ThrowStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(&stmt.value)?;
+        Err(LoxResult::throw(stmt.keyword.clone(), value))
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &TryStmt) -> Result<(), LoxResult> {
+        let environment = Environment::new_with_enclosing(Rc::clone(&self.environment));
+        let mut result = self.execute_block(&stmt.body, environment);
+
+        if let (Err(error), Some(catch_body)) = (&result, &stmt.catch_body) {
+            // Only errors are caught, returns and loop control flow go straight through
+            if let Some(value) = self.caught_value(error) {
+                let mut environment = Environment::new_with_enclosing(Rc::clone(&self.environment));
+                if let Some(name) = &stmt.catch_name {
+                    environment.bind(&name.lexeme, value);
+                }
+                result = self.execute_block(catch_body, environment);
+            }
+        }
+
+        // Anything that escapes the finally block replaces whatever the try or catch block did
+        if let Some(finally_body) = &stmt.finally_body {
+            let environment = Environment::new_with_enclosing(Rc::clone(&self.environment));
+            self.execute_block(finally_body, environment)?;
+        }
+        result
+    }
+
+    fn visit_destructure_stmt(&mut self, stmt: &DestructureStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(&stmt.initializer)?;
+        stmt.pattern.destructure(
+            Some(&value),
+            &mut self.environment.borrow_mut(),
+            &stmt.equals,
+        )
+    }
+
+    fn visit_enum_stmt(&mut self, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        let lox_enum = LoxEnum::new(&stmt.name.lexeme, &stmt.variants);
+        self.environment
+            .borrow_mut()
+            .define(&stmt.name, Lit::Enum(Rc::new(lox_enum)))
+    }
+
     fn visit_var_stmt(&mut self, stmt: &VarStmt) -> Result<(), LoxResult> {
         let value = if let Some(init) = &stmt.initializer {
             Some(self.evaluate(init)?)
@@ -66,55 +335,169 @@ impl StmtVisitor<()> for Interpreter {
 
         self.environment
             .borrow_mut()
-            .define(&stmt.name.lexeme, value.unwrap_or(Lit::Nil));
-        Ok(())
+            .define(&stmt.name, value.unwrap_or(Lit::Nil))
     }
 }
 
 impl ExprVisitor<Lit> for Interpreter {
     fn visit_call_expr(&mut self, expr: &CallExpr) -> Result<Lit, LoxResult> {
         let callee = self.evaluate(&expr.callee)?;
+        self.call_value(callee, &expr.arguments, &expr.paren)
+    }
 
-        let mut arguments = vec![];
-        for argument in &expr.arguments {
-            arguments.push(self.evaluate(argument)?);
+    fn visit_chain_expr(&mut self, expr: &ChainExpr) -> Result<Lit, LoxResult> {
+        match self.evaluate(&expr.expression) {
+            Err(LoxResult::ShortCircuit) => Ok(Lit::Nil),
+            result => result,
         }
+    }
 
-        let callfunc: Option<Rc<dyn LoxCallable>> = match callee {
-            Lit::Func(f) => Some(f),
-            Lit::Native(n) => Some(n.func.clone()),
-            _ => None,
-        };
+    fn visit_optionalcall_expr(&mut self, expr: &OptionalCallExpr) -> Result<Lit, LoxResult> {
+        match self.evaluate(&expr.callee)? {
+            Lit::Nil => Err(LoxResult::ShortCircuit),
+            callee => self.call_value(callee, &expr.arguments, &expr.paren),
+        }
+    }
 
-        if let Some(callfunc) = callfunc {
-            if arguments.len() != callfunc.arity() {
+    fn visit_get_expr(&mut self, expr: &GetExpr) -> Result<Lit, LoxResult> {
+        let object = self.evaluate(&expr.object)?;
+        self.get_property(object, &expr.name)
+    }
+
+    fn visit_optionalget_expr(&mut self, expr: &OptionalGetExpr) -> Result<Lit, LoxResult> {
+        match self.evaluate(&expr.object)? {
+            Lit::Nil => Err(LoxResult::ShortCircuit),
+            object => self.get_property(object, &expr.name),
+        }
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &ConditionalExpr) -> Result<Lit, LoxResult> {
+        if self.evaluate(&expr.condition)?.is_truthy() {
+            self.evaluate(&expr.then_branch)
+        } else {
+            self.evaluate(&expr.else_branch)
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &SetExpr) -> Result<Lit, LoxResult> {
+        let object = self.evaluate(&expr.object)?;
+        let instance = expect_instance(object, &expr.name)?;
+        let value = self.evaluate(&expr.value)?;
+        self.set_property(instance, &expr.name, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_compound_expr(&mut self, expr: &CompoundExpr) -> Result<Lit, LoxResult> {
+        // Each kind of target evaluates its parts once, and then reads and writes through them
+        let (old, new) = match expr.target.as_ref() {
+            Expr::Variable(v) => {
+                let old = self.look_up_variable(&v.name, v.depth.get())?;
+                let new = self.compound_value(expr, old.clone())?;
+                self.assign_variable(&v.name, v.depth.get(), new.clone())?;
+                (old, new)
+            }
+            Expr::Get(g) => {
+                let object = self.evaluate(&g.object)?;
+                let old = self.get_property(object.clone(), &g.name)?;
+                let new = self.compound_value(expr, old.clone())?;
+                let instance = expect_instance(object, &g.name)?;
+                self.set_property(instance, &g.name, new.clone())?;
+                (old, new)
+            }
+            Expr::Index(i) => {
+                let object = self.evaluate(&i.object)?;
+                let index = self.evaluate(&i.index)?;
+                let old = index_get(object.clone(), index.clone(), &i.bracket)?;
+                let new = self.compound_value(expr, old.clone())?;
+                index_set(object, index, &i.bracket, new.clone())?;
+                (old, new)
+            }
+            _ => {
                 return Err(LoxResult::runtime_error(
-                    expr.paren.clone(),
-                    &format!(
-                        "Expected {} arguments, but got {}",
-                        callfunc.arity(),
-                        arguments.len()
-                    ),
-                ));
+                    expr.operator.clone(),
+                    "Invalid assignment target.",
+                ))
             }
+        };
 
-            callfunc.call(self, arguments)
-        } else {
-            Err(LoxResult::runtime_error(
-                expr.paren.clone(),
-                "Can only call functions and classes.",
-            ))
+        Ok(if expr.postfix { old } else { new })
+    }
+
+    fn visit_super_expr(&mut self, expr: &SuperExpr) -> Result<Lit, LoxResult> {
+        let distance = expr.depth.get().ok_or_else(|| {
+            LoxResult::runtime_error(expr.keyword.clone(), "Unresolved 'super' expression.")
+        })?;
+        let superclass = self.environment.borrow().get_at(distance, &expr.keyword)?;
+
+        // `this` is always bound in the environment right inside the one holding `super`
+        let this = Token::new(TokenType::This, "this", None, expr.keyword.line);
+        let object = self.environment.borrow().get_at(distance - 1, &this)?;
+
+        match (superclass, object) {
+            (Lit::Class(superclass), Lit::Instance(instance)) => {
+                // Like on the instance itself, getters come before methods
+                if let Some(getter) = superclass.find_getter(&expr.method.lexeme) {
+                    return getter.bind(instance).call(self, Vec::new(), &expr.method);
+                }
+                match superclass.find_method(&expr.method.lexeme) {
+                    Some(method) => Ok(Lit::Func(Rc::new(method.bind(instance)))),
+                    None => Err(LoxResult::runtime_error(
+                        expr.method.clone(),
+                        &format!("Undefined property '{}'.", expr.method.lexeme),
+                    )),
+                }
+            }
+            _ => Err(LoxResult::runtime_error(
+                expr.keyword.clone(),
+                "Invalid 'super' expression.",
+            )),
+        }
+    }
+
+    fn visit_this_expr(&mut self, expr: &ThisExpr) -> Result<Lit, LoxResult> {
+        self.look_up_variable(&expr.keyword, expr.depth.get())
+    }
+
+    fn visit_index_expr(&mut self, expr: &IndexExpr) -> Result<Lit, LoxResult> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        index_get(object, index, &expr.bracket)
+    }
+
+    fn visit_indexset_expr(&mut self, expr: &IndexSetExpr) -> Result<Lit, LoxResult> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        let value = self.evaluate(&expr.value)?;
+        index_set(object, index, &expr.bracket, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_list_expr(&mut self, expr: &ListExpr) -> Result<Lit, LoxResult> {
+        let mut elements = Vec::with_capacity(expr.elements.len());
+        for element in &expr.elements {
+            elements.push(self.evaluate(element)?);
+        }
+        Ok(Lit::List(Rc::new(RefCell::new(elements))))
+    }
+
+    fn visit_map_expr(&mut self, expr: &MapExpr) -> Result<Lit, LoxResult> {
+        let mut map = HashMap::with_capacity(expr.keys.len());
+        for (key, value) in expr.keys.iter().zip(&expr.values) {
+            let key = MapKey::from_lit(&self.evaluate(key)?, &expr.brace)?;
+            map.insert(key, self.evaluate(value)?);
         }
+        Ok(Lit::Map(Rc::new(RefCell::new(map))))
     }
 
     fn visit_logical_expr(&mut self, expr: &LogicalExpr) -> Result<Lit, LoxResult> {
         let left = self.evaluate(&expr.left)?;
 
-        if expr.operator.ttype == TokenType::Or {
-            if left.is_truthy() {
-                return Ok(left);
-            }
-        } else if left.is_truthy() {
+        let short_circuit = match expr.operator.ttype {
+            TokenType::Or => left.is_truthy(),
+            TokenType::QuestionQuestion => left != Lit::Nil,
+            _ => !left.is_truthy(),
+        };
+        if short_circuit {
             return Ok(left);
         }
 
@@ -124,73 +507,7 @@ impl ExprVisitor<Lit> for Interpreter {
     fn visit_binary_expr(&mut self, expr: &BinaryExpr) -> Result<Lit, LoxResult> {
         let left = self.evaluate(&expr.left)?;
         let right = self.evaluate(&expr.right)?;
-
-        // This still doesnt work with EqualEqual, kek
-        match expr.operator.ttype {
-            TokenType::Plus => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left + right)),
-                (Lit::Str(left), Lit::Str(right)) => Ok(Lit::Str(format!("{}{}", left, right))),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers or two strings.",
-                )),
-            },
-            TokenType::Minus => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left - right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::Star => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left * right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::Slash => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left / right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::Greater => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left > right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::GreaterEqual => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left >= right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::Less => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left < right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::LessEqual => match (left, right) {
-                (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left <= right)),
-                _ => Err(LoxResult::runtime_error(
-                    expr.operator.clone(),
-                    "Expected two numbers.",
-                )),
-            },
-            TokenType::EqualEqual => Ok(Lit::Bool(left == right)),
-            TokenType::BangEqual => Ok(Lit::Bool(left != right)),
-            _ => Err(LoxResult::runtime_error(
-                expr.operator.clone(),
-                "Illegal expression.",
-            )),
-        }
+        binary_op(&expr.operator, &expr.operator.ttype, left, right)
     }
     fn visit_unary_expr(&mut self, expr: &UnaryExpr) -> Result<Lit, LoxResult> {
         let right = self.evaluate(&expr.right)?;
@@ -211,35 +528,263 @@ impl ExprVisitor<Lit> for Interpreter {
     fn visit_grouping_expr(&mut self, expr: &GroupingExpr) -> Result<Lit, LoxResult> {
         self.evaluate(&expr.expression)
     }
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr) -> Result<Lit, LoxResult> {
+        let function = LoxFunction::new(
+            &expr.declaration,
+            Rc::clone(&self.environment),
+            Rc::clone(&self.globals),
+            false,
+        );
+        Ok(Lit::Func(Rc::new(function)))
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &InterpolationExpr) -> Result<Lit, LoxResult> {
+        let mut result = String::new();
+        for part in &expr.parts {
+            match self.evaluate(part)? {
+                // Strings go in as-is, without the quotes they are displayed with
+                Lit::Str(s) => result.push_str(&s),
+                value => result.push_str(&value.to_string()),
+            }
+        }
+        Ok(Lit::Str(result))
+    }
     fn visit_literal_expr(&mut self, expr: &LiteralExpr) -> Result<Lit, LoxResult> {
         Ok(expr.value.clone().unwrap())
     }
     fn visit_variable_expr(&mut self, expr: &VariableExpr) -> Result<Lit, LoxResult> {
-        self.environment.borrow().get(&expr.name)
+        self.look_up_variable(&expr.name, expr.depth.get())
+    }
+    fn visit_destructure_expr(&mut self, expr: &DestructureExpr) -> Result<Lit, LoxResult> {
+        let value = self.evaluate(&expr.value)?;
+        // Take the value apart first, so that `[a, b] = [b, a]` swaps them
+        let mut parts = Environment::new();
+        expr.pattern
+            .destructure(Some(&value), &mut parts, &expr.equals)?;
+        for (name, depth) in expr.pattern.bindings().into_iter().zip(&expr.depths) {
+            let part = parts.get_own(&name.lexeme).unwrap_or(Lit::Nil);
+            self.assign_variable(name, depth.get(), part)?;
+        }
+        Ok(value)
     }
+
     fn visit_assign_expr(&mut self, expr: &AssignExpr) -> Result<Lit, LoxResult> {
         let value = self.evaluate(&expr.value)?;
-        self.environment
-            .borrow_mut()
-            .assign(&expr.name, value.clone())?;
+        self.assign_variable(&expr.name, expr.depth.get(), value.clone())?;
         Ok(value)
     }
 }
 
+/// Scans, parses and resolves the source of a module, or returns `None` if any of that failed
+fn compile(source: String) -> Option<Vec<Stmt>> {
+    let tokens = Scanner::new(source).scan_tokens().ok()?;
+    let mut parser = Parser::new(tokens);
+    let statements = parser.parse().ok()?;
+    if !parser.success() {
+        return None;
+    }
+
+    let mut resolver = Resolver::new();
+    resolver.resolve(&statements).ok()?;
+    resolver.success().then_some(statements)
+}
+
+fn expect_instance(object: Lit, name: &Token) -> Result<Rc<LoxInstance>, LoxResult> {
+    match object {
+        Lit::Instance(instance) => Ok(instance),
+        _ => Err(LoxResult::runtime_error(
+            name.clone(),
+            "Only instances have fields.",
+        )),
+    }
+}
+
+fn index_get(object: Lit, index: Lit, bracket: &Token) -> Result<Lit, LoxResult> {
+    match object {
+        Lit::List(list) => {
+            let list = list.borrow();
+            let i = index.to_index(bracket, list.len())?;
+            Ok(list[i].clone())
+        }
+        Lit::Map(map) => {
+            let key = MapKey::from_lit(&index, bracket)?;
+            map.borrow().get(&key).cloned().ok_or_else(|| {
+                LoxResult::runtime_error(
+                    bracket.clone(),
+                    &format!("Key {} not found in map.", index),
+                )
+            })
+        }
+        _ => Err(LoxResult::runtime_error(
+            bracket.clone(),
+            "Only lists and maps can be indexed.",
+        )),
+    }
+}
+
+fn index_set(object: Lit, index: Lit, bracket: &Token, value: Lit) -> Result<(), LoxResult> {
+    match object {
+        Lit::List(list) => {
+            let mut list = list.borrow_mut();
+            let i = index.to_index(bracket, list.len())?;
+            list[i] = value;
+            Ok(())
+        }
+        Lit::Map(map) => {
+            let key = MapKey::from_lit(&index, bracket)?;
+            map.borrow_mut().insert(key, value);
+            Ok(())
+        }
+        _ => Err(LoxResult::runtime_error(
+            bracket.clone(),
+            "Only lists and maps can be indexed.",
+        )),
+    }
+}
+
+/// Applies a binary operator to two already evaluated operands. `ttype` is passed separately
+/// from `operator` so that compound assignments can reuse the arithmetic ones.
+fn binary_op(operator: &Token, ttype: &TokenType, left: Lit, right: Lit) -> Result<Lit, LoxResult> {
+    // This still doesnt work with EqualEqual, kek
+    match ttype {
+        TokenType::Plus => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left + right)),
+            (Lit::Str(left), Lit::Str(right)) => Ok(Lit::Str(format!("{}{}", left, right))),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers or two strings.",
+            )),
+        },
+        TokenType::Minus => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left - right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::Star => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left * right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::Slash => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left / right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        // Floored modulo, the result takes the sign of the divisor like with `~/`
+        TokenType::Percent => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => {
+                let rem = left % right;
+                if rem != 0.0 && (rem < 0.0) != (right < 0.0) {
+                    Ok(Lit::Num(rem + right))
+                } else {
+                    Ok(Lit::Num(rem))
+                }
+            }
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::TildeSlash => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num((left / right).floor())),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::StarStar => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Num(left.powf(right))),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::Greater => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left > right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::GreaterEqual => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left >= right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::Less => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left < right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::LessEqual => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Bool(left <= right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Expected two numbers.",
+            )),
+        },
+        TokenType::DotDot => match (left, right) {
+            (Lit::Num(left), Lit::Num(right)) => Ok(Lit::Range(left, right)),
+            _ => Err(LoxResult::runtime_error(
+                operator.clone(),
+                "Range bounds must be numbers.",
+            )),
+        },
+        TokenType::EqualEqual => Ok(Lit::Bool(left == right)),
+        TokenType::BangEqual => Ok(Lit::Bool(left != right)),
+        _ => Err(LoxResult::runtime_error(
+            operator.clone(),
+            "Illegal expression.",
+        )),
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
-        let globals = Rc::new(RefCell::new(Environment::new()));
+        let builtins = Rc::new(RefCell::new(Environment::new()));
 
-        globals.borrow_mut().define(
-            "clock",
-            Lit::Native(Rc::new(LoxNative {
-                func: Rc::new(NativeClock {}),
-            })),
-        );
+        let natives: [(&str, Rc<dyn LoxCallable>); 9] = [
+            ("clock", Rc::new(NativeClock {})),
+            ("len", Rc::new(NativeLen {})),
+            ("push", Rc::new(NativePush {})),
+            ("pop", Rc::new(NativePop {})),
+            ("insert", Rc::new(NativeInsert {})),
+            ("keys", Rc::new(NativeKeys {})),
+            ("values", Rc::new(NativeValues {})),
+            ("has", Rc::new(NativeHas {})),
+            ("remove", Rc::new(NativeRemove {})),
+        ];
+        for (name, func) in natives {
+            builtins
+                .borrow_mut()
+                .bind(name, Lit::Native(Rc::new(LoxNative { func })));
+        }
+
+        let error_class = LoxClass::new("Error", None, Members::default());
+        builtins
+            .borrow_mut()
+            .bind("Error", Lit::Class(Rc::clone(&error_class)));
 
+        let globals = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &builtins,
+        ))));
         Self {
             globals: Rc::clone(&globals),
             environment: Rc::clone(&globals),
+            builtins,
+            error_class,
+            modules: HashMap::new(),
+            directory: PathBuf::from("."),
         }
     }
 
@@ -251,7 +796,31 @@ impl Interpreter {
         statement.accept(self)
     }
 
-    fn execute_block(
+    /// Executes `statement` in `environment` instead of the current one, which is how generators
+    /// resume in the scope they were suspended in
+    pub fn execute_in(
+        &mut self,
+        statement: &Stmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), LoxResult> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute(statement);
+        self.environment = previous;
+        result
+    }
+
+    pub fn evaluate_in(
+        &mut self,
+        expr: &Expr,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Lit, LoxResult> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result
+    }
+
+    pub fn execute_block(
         &mut self,
         statements: &[Stmt],
         environment: Environment,
@@ -264,24 +833,349 @@ impl Interpreter {
         result
     }
 
-    /// Returns `true` on success
-    pub fn interpret(&mut self, statements: &[Stmt]) -> bool {
-        for statement in statements {
-            if self.execute(statement).is_err() {
-                return false;
+    fn call_value(
+        &mut self,
+        callee: Lit,
+        arguments: &[Argument],
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let mut positional = vec![];
+        let mut named = vec![];
+        for argument in arguments {
+            let value = self.evaluate(&argument.value)?;
+            match &argument.name {
+                Some(name) => named.push((name.clone(), value)),
+                None => positional.push(value),
             }
         }
-        true
+        self.call_with_named(callee, positional, named, paren)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::token::Token;
 
-    use super::*;
+    pub fn call_with_values(
+        &mut self,
+        callee: Lit,
+        values: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        self.call_with_named(callee, values, Vec::new(), paren)
+    }
 
-    fn make_literal_num_expr(i: f64) -> Box<Expr> {
+    /// Calls `callee` once its positional and named arguments are bound to its signature
+    pub fn call_with_named(
+        &mut self,
+        callee: Lit,
+        positional: Vec<Lit>,
+        named: Vec<(Token, Lit)>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let callfunc: Option<Rc<dyn LoxCallable>> = match callee {
+            Lit::Func(f) => Some(f),
+            Lit::Native(n) => Some(n.func.clone()),
+            Lit::Class(c) => Some(c),
+            _ => None,
+        };
+
+        if let Some(callfunc) = callfunc {
+            let arguments = callfunc.signature().bind(positional, named, paren)?;
+            callfunc.call_bound(self, arguments, paren)
+        } else {
+            Err(LoxResult::runtime_error(
+                paren.clone(),
+                "Can only call functions and classes.",
+            ))
+        }
+    }
+
+    /// Finds the first arm of a match statement run in `environment` that matches and whose guard
+    /// passes. Returns its index along with the scope its bindings are defined in.
+    pub fn match_arm(
+        &mut self,
+        stmt: &MatchStmt,
+        environment: &Rc<RefCell<Environment>>,
+    ) -> Result<(usize, Rc<RefCell<Environment>>), LoxResult> {
+        let value = self.evaluate_in(&stmt.subject, Rc::clone(environment))?;
+        for (i, arm) in stmt.arms.iter().enumerate() {
+            let mut scope = Environment::new_with_enclosing(Rc::clone(environment));
+            if !arm.pattern.matches(&value, &mut scope) {
+                continue;
+            }
+
+            let scope = Rc::new(RefCell::new(scope));
+            if let Some(guard) = &arm.guard {
+                if !self.evaluate_in(guard, Rc::clone(&scope))?.is_truthy() {
+                    continue;
+                }
+            }
+            return Ok((i, scope));
+        }
+
+        Err(LoxResult::runtime_error(
+            stmt.keyword.clone(),
+            &format!("No match arm matches {}.", value),
+        ))
+    }
+
+    /// Runs the body of a for-in loop with the loop variable freshly bound to `value`, and returns
+    /// `false` if the loop should stop
+    fn execute_loop_body(&mut self, stmt: &ForInStmt, value: Lit) -> Result<bool, LoxResult> {
+        let mut environment = Environment::new_with_enclosing(Rc::clone(&self.environment));
+        environment.bind(&stmt.name.lexeme, value);
+        match self.execute_block(std::slice::from_ref(&stmt.body), environment) {
+            Err(LoxResult::Break) => Ok(false),
+            Err(LoxResult::Continue) | Ok(()) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_property(&mut self, object: Lit, name: &Token) -> Result<Lit, LoxResult> {
+        match object {
+            Lit::Instance(instance) => match instance.class.find_getter(&name.lexeme) {
+                Some(getter) => getter.bind(instance).call(self, Vec::new(), name),
+                None => instance.get(name),
+            },
+            Lit::Class(class) => match class.find_static(&name.lexeme) {
+                Some(method) => Ok(Lit::Func(method)),
+                None => Err(LoxResult::runtime_error(
+                    name.clone(),
+                    &format!("Undefined static method '{}'.", name.lexeme),
+                )),
+            },
+            Lit::Module(module) => module.get(name),
+            Lit::Enum(lox_enum) => lox_enum.get(name),
+            Lit::Variant(variant) => variant.get(name),
+            Lit::Generator(generator) => match name.lexeme.as_str() {
+                "next" => Ok(Lit::Native(Rc::new(LoxNative {
+                    func: Rc::new(GeneratorNext { generator }),
+                }))),
+                "done" => Ok(Lit::Bool(generator.is_done())),
+                _ => Err(LoxResult::runtime_error(
+                    name.clone(),
+                    &format!("Undefined property '{}'.", name.lexeme),
+                )),
+            },
+            _ => Err(LoxResult::runtime_error(
+                name.clone(),
+                "Only instances have properties.",
+            )),
+        }
+    }
+
+    /// Assigns to a field of the instance, unless its class has a setter for it
+    fn set_property(
+        &mut self,
+        instance: Rc<LoxInstance>,
+        name: &Token,
+        value: Lit,
+    ) -> Result<(), LoxResult> {
+        if let Some(setter) = instance.class.find_setter(&name.lexeme) {
+            setter.bind(instance).call(self, vec![value], name)?;
+        } else if instance.class.find_getter(&name.lexeme).is_some() {
+            return Err(LoxResult::runtime_error(
+                name.clone(),
+                &format!("Property '{}' has a getter but no setter.", name.lexeme),
+            ));
+        } else {
+            instance.set(name, value);
+        }
+        Ok(())
+    }
+
+    /// Evaluates the right hand side of a compound assignment and combines it with the target's
+    /// current value
+    fn compound_value(&mut self, expr: &CompoundExpr, old: Lit) -> Result<Lit, LoxResult> {
+        let ttype = match expr.operator.ttype {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    expr.operator.clone(),
+                    "Illegal expression.",
+                ))
+            }
+        };
+        let value = self.evaluate(&expr.value)?;
+        binary_op(&expr.operator, &ttype, old, value)
+    }
+
+    fn assign_variable(
+        &mut self,
+        name: &Token,
+        depth: Option<usize>,
+        value: Lit,
+    ) -> Result<(), LoxResult> {
+        if let Some(distance) = depth {
+            self.environment
+                .borrow_mut()
+                .assign_at(distance, name, value)
+        } else {
+            self.globals.borrow_mut().assign(name, value)
+        }
+    }
+
+    fn look_up_variable(&self, name: &Token, depth: Option<usize>) -> Result<Lit, LoxResult> {
+        if let Some(distance) = depth {
+            self.environment.borrow().get_at(distance, name)
+        } else {
+            self.globals.borrow().get(name)
+        }
+    }
+
+    /// Makes imports relative to the script at `path`, which also counts as running so that
+    /// importing it back is reported as a cycle
+    pub fn set_script(&mut self, path: &Path) {
+        if let Ok(path) = path.canonicalize() {
+            if let Some(directory) = path.parent() {
+                self.directory = directory.to_path_buf();
+            }
+            self.modules.insert(path, None);
+        }
+    }
+
+    /// Runs the module at `path` in its own global scope the first time it's imported, and
+    /// returns the cached module after that
+    fn import(&mut self, path: &Token) -> Result<Rc<LoxModule>, LoxResult> {
+        let name = match &path.literal {
+            Some(Lit::Str(name)) => name.clone(),
+            _ => {
+                return Err(LoxResult::runtime_error(
+                    path.clone(),
+                    "Module path must be a string.",
+                ))
+            }
+        };
+
+        let file = self.directory.join(&name).canonicalize().map_err(|e| {
+            LoxResult::runtime_error(
+                path.clone(),
+                &format!("Can't find module '{}': {}.", name, e),
+            )
+        })?;
+        match self.modules.get(&file) {
+            Some(Some(module)) => return Ok(Rc::clone(module)),
+            Some(None) => {
+                return Err(LoxResult::runtime_error(
+                    path.clone(),
+                    &format!("Import cycle detected while importing '{}'.", name),
+                ))
+            }
+            None => {}
+        }
+
+        let source = fs::read_to_string(&file).map_err(|e| {
+            LoxResult::runtime_error(
+                path.clone(),
+                &format!("Can't read module '{}': {}.", name, e),
+            )
+        })?;
+        // Any errors were already reported while compiling
+        let statements = compile(source).ok_or_else(|| {
+            LoxResult::runtime_error(path.clone(), &format!("Can't compile module '{}'.", name))
+        })?;
+
+        let globals = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &self.builtins,
+        ))));
+        let directory = file.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        self.modules.insert(file.clone(), None);
+
+        let previous_globals = std::mem::replace(&mut self.globals, Rc::clone(&globals));
+        let previous_environment = std::mem::replace(&mut self.environment, Rc::clone(&globals));
+        let previous_directory = std::mem::replace(&mut self.directory, directory);
+        let result = statements.iter().try_for_each(|s| self.execute(s));
+        self.globals = previous_globals;
+        self.environment = previous_environment;
+        self.directory = previous_directory;
+
+        if let Err(e) = result {
+            self.modules.remove(&file);
+            return Err(e);
+        }
+
+        let module = Rc::new(LoxModule::new(&name, globals));
+        self.modules.insert(file, Some(Rc::clone(&module)));
+        Ok(module)
+    }
+
+    /// The value a `catch` block sees for `error`, or `None` if it can't be caught. Runtime errors
+    /// become `Error` instances with a `message` and a `line` field, which remember the error.
+    fn caught_value(&self, error: &LoxResult) -> Option<Lit> {
+        match error {
+            LoxResult::Throw { value, .. } => Some(value.clone()),
+            LoxResult::RuntimeError { token, message } => {
+                let instance = Rc::new(LoxInstance::from_error(
+                    Rc::clone(&self.error_class),
+                    LoxResult::runtime_error(token.clone(), message),
+                ));
+                let field = |name| Token::new(TokenType::Identifier, name, None, token.line);
+                instance.set(&field("message"), Lit::Str(message.clone()));
+                instance.set(&field("line"), Lit::Num(token.line as f64));
+                Some(Lit::Instance(instance))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` on success
+    pub fn interpret(&mut self, statements: &[Stmt]) -> bool {
+        for statement in statements {
+            if let Err(error) = self.execute(statement) {
+                // Runtime errors are only reported once it's clear that nothing catches them
+                if matches!(
+                    error,
+                    LoxResult::RuntimeError { .. } | LoxResult::Throw { .. }
+                ) {
+                    error.report("");
+                }
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_source(source: &str) -> Vec<Stmt> {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+        assert!(parser.success());
+        statements
+    }
+
+    fn resolves(statements: &[Stmt]) -> bool {
+        let mut resolver = Resolver::new();
+        resolver.resolve(statements).unwrap();
+        resolver.success()
+    }
+
+    fn fails_to_parse(source: &str) -> bool {
+        let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().is_err() || !parser.success()
+    }
+
+    fn run_source(source: &str) -> Interpreter {
+        let statements = parse_source(source);
+        assert!(resolves(&statements));
+        let mut interpreter = Interpreter::new();
+        assert!(interpreter.interpret(&statements));
+        interpreter
+    }
+
+    fn global(interpreter: &Interpreter, name: &str) -> Lit {
+        interpreter
+            .globals
+            .borrow()
+            .get(&Token::new(TokenType::Identifier, name, None, 0))
+            .unwrap()
+    }
+
+    fn make_literal_num_expr(i: f64) -> Box<Expr> {
         Box::new(Expr::Literal(LiteralExpr {
             value: Some(Lit::Num(i)),
         }))
@@ -492,4 +1386,1189 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.ok(), Some(Lit::Bool(true)));
     }
+
+    #[test]
+    fn test_function_call_binds_params() {
+        let interpreter = run_source(
+            "var result;
+            fun add(a, b) { result = a + b; }
+            add(1, 2);",
+        );
+        assert_eq!(global(&interpreter, "result"), Lit::Num(3.0));
+    }
+
+    #[test]
+    fn test_function_closure_keeps_environment() {
+        let interpreter = run_source(
+            "var result;
+            var counter;
+            fun outer() {
+                var count = 0;
+                fun inner() {
+                    count = count + 1;
+                    result = count;
+                }
+                counter = inner;
+            }
+            outer();
+            counter();
+            counter();",
+        );
+        assert_eq!(global(&interpreter, "result"), Lit::Num(2.0));
+    }
+
+    #[test]
+    fn test_function_wrong_arity() {
+        let statements = parse_source("fun f(a) {} f(1, 2);");
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_return_value() {
+        let interpreter = run_source(
+            "fun fib(n) {
+                if (n < 2) return n;
+                return fib(n - 1) + fib(n - 2);
+            }
+            var result = fib(10);",
+        );
+        assert_eq!(global(&interpreter, "result"), Lit::Num(55.0));
+    }
+
+    #[test]
+    fn test_return_unwinds_loops() {
+        let interpreter = run_source(
+            "fun find() {
+                for (var i = 0; i < 10; i = i + 1) {
+                    while (true) {
+                        if (i == 3) return i;
+                        return nil;
+                    }
+                }
+            }
+            fun first() {
+                var i = 0;
+                while (true) {
+                    { if (i == 4) return i; }
+                    i = i + 1;
+                }
+            }
+            fun nothing() { return; }
+            var a = find();
+            var b = first();
+            var c = nothing();",
+        );
+        assert_eq!(global(&interpreter, "a"), Lit::Nil);
+        assert_eq!(global(&interpreter, "b"), Lit::Num(4.0));
+        assert_eq!(global(&interpreter, "c"), Lit::Nil);
+    }
+
+    #[test]
+    fn test_top_level_return_is_rejected() {
+        assert!(!resolves(&parse_source("return 1;")));
+    }
+
+    #[test]
+    fn test_closure_ignores_later_shadowing() {
+        let interpreter = run_source(
+            "var a = \"global\";
+            var first;
+            var second;
+            {
+                fun show() { return a; }
+                first = show();
+                var a = \"block\";
+                second = show();
+            }",
+        );
+        assert_eq!(
+            global(&interpreter, "first"),
+            Lit::Str("global".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "second"),
+            Lit::Str("global".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolver_static_errors() {
+        assert!(!resolves(&parse_source("{ var a = a; }")));
+        assert!(!resolves(&parse_source("{ var a = 1; var a = 2; }")));
+        assert!(!resolves(&parse_source("fun f(a, a) {}")));
+        assert!(resolves(&parse_source("var a = 1; var a = a;")));
+    }
+
+    #[test]
+    fn test_class_fields_and_methods() {
+        let interpreter = run_source(
+            "class Counter {
+                init(start) {
+                    this.count = start;
+                }
+                bump() {
+                    this.count = this.count + 1;
+                    return this;
+                }
+            }
+            var counter = Counter(10);
+            counter.bump().bump();
+            var method = counter.bump;
+            method();
+            var result = counter.count;
+            var reinit = counter.init(1) == counter;",
+        );
+        assert_eq!(global(&interpreter, "result"), Lit::Num(13.0));
+        assert_eq!(global(&interpreter, "reinit"), Lit::Bool(true));
+    }
+
+    #[test]
+    fn test_class_initializer_arity() {
+        let statements = parse_source("class A { init(a, b) {} } A(1);");
+        assert!(resolves(&statements));
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_class_static_errors() {
+        assert!(!resolves(&parse_source("print this;")));
+        assert!(!resolves(&parse_source("fun f() { return this; }")));
+        assert!(!resolves(&parse_source("class A { init() { return 1; } }")));
+        assert!(resolves(&parse_source("class A { init() { return; } }")));
+    }
+
+    #[test]
+    fn test_inherited_and_super_methods() {
+        let interpreter = run_source(
+            "class A {
+                init(name) { this.name = name; }
+                describe() { return \"A \" + this.name; }
+                kind() { return \"a\"; }
+            }
+            class B < A {
+                describe() { return super.describe() + \" via B\"; }
+            }
+            class C < B {}
+            var c = C(\"c\");
+            var described = c.describe();
+            var kind = c.kind();",
+        );
+        assert_eq!(
+            global(&interpreter, "described"),
+            Lit::Str("A c via B".to_string())
+        );
+        assert_eq!(global(&interpreter, "kind"), Lit::Str("a".to_string()));
+    }
+
+    #[test]
+    fn test_superclass_must_be_class() {
+        let statements = parse_source("var A = 1; class B < A {}");
+        assert!(resolves(&statements));
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_super_static_errors() {
+        assert!(!resolves(&parse_source("class A < A {}")));
+        assert!(!resolves(&parse_source("class A { f() { super.f(); } }")));
+        assert!(!resolves(&parse_source("fun f() { super.f(); }")));
+    }
+
+    #[test]
+    fn test_list_index_and_natives() {
+        let interpreter = run_source(
+            "var xs = [1, 2, 3];
+            xs[0] = 10;
+            push(xs, 4);
+            insert(xs, 1, 5);
+            var last = pop(xs);
+            var first = xs[0];
+            var second = xs[1];
+            var length = len(xs);
+            var nested = [[1], []];
+            var empty = len(nested[1]);
+            var same = [1, \"a\"] == [1, \"a\"];
+            var looped = [1];
+            push(looped, looped);
+            var itself = looped == looped;
+            var table = {\"list\": looped};
+            table[\"table\"] = table;
+            var shown = \"${looped} ${table}\";",
+        );
+        assert_eq!(global(&interpreter, "last"), Lit::Num(4.0));
+        assert_eq!(global(&interpreter, "first"), Lit::Num(10.0));
+        assert_eq!(global(&interpreter, "second"), Lit::Num(5.0));
+        assert_eq!(global(&interpreter, "length"), Lit::Num(4.0));
+        assert_eq!(global(&interpreter, "empty"), Lit::Num(0.0));
+        assert_eq!(global(&interpreter, "same"), Lit::Bool(true));
+        assert_eq!(global(&interpreter, "itself"), Lit::Bool(true));
+        assert_eq!(
+            global(&interpreter, "shown"),
+            Lit::Str("[1, [...]] {\"list\": [1, [...]], \"table\": {...}}".to_string())
+        );
+    }
+
+    #[test]
+    fn test_list_index_errors() {
+        for source in [
+            "[1, 2][2];",
+            "[1, 2][-1];",
+            "[1, 2][0.5];",
+            "[1, 2][\"0\"] = 1;",
+            "pop([]);",
+            "1[0];",
+        ] {
+            let statements = parse_source(source);
+            assert!(resolves(&statements));
+            assert!(!Interpreter::new().interpret(&statements), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_map_literal_lookup_and_natives() {
+        let interpreter = run_source(
+            "var m = {\"name\": \"lox\", 1: true, nil: 0};
+            m[\"age\"] = 3;
+            m[-0] = \"zero\";
+            var zero = m[0];
+            var name = m[\"name\"];
+            var had = has(m, \"age\");
+            var removed = remove(m, \"age\");
+            var missing = remove(m, \"age\");
+            var hasAfter = has(m, \"age\");
+            var size = len(m);
+            var ks = keys(m);
+            var vs = values({\"b\": 2, \"a\": 1});
+            var empty = len({});",
+        );
+        assert_eq!(global(&interpreter, "zero"), Lit::Str("zero".to_string()));
+        assert_eq!(global(&interpreter, "name"), Lit::Str("lox".to_string()));
+        assert_eq!(global(&interpreter, "had"), Lit::Bool(true));
+        assert_eq!(global(&interpreter, "removed"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "missing"), Lit::Nil);
+        assert_eq!(global(&interpreter, "hasAfter"), Lit::Bool(false));
+        assert_eq!(global(&interpreter, "size"), Lit::Num(4.0));
+        assert_eq!(
+            global(&interpreter, "ks").to_string(),
+            "[nil, 0, 1, \"name\"]"
+        );
+        assert_eq!(global(&interpreter, "vs").to_string(), "[1, 2]");
+        assert_eq!(global(&interpreter, "empty"), Lit::Num(0.0));
+    }
+
+    #[test]
+    fn test_map_key_errors() {
+        for source in [
+            "var m = {}; m[0 / 0] = 1;",
+            "var m = {}; m[[]] = 1;",
+            "var m = {\"a\": 1}; m[\"b\"];",
+        ] {
+            let statements = parse_source(source);
+            assert!(resolves(&statements));
+            assert!(!Interpreter::new().interpret(&statements), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let interpreter = run_source(
+            r#"var escaped = "a\n\t\r\\\"\0b";
+            var unicode = "\u{48}\u{e9}\u{1F600}";
+            /* multi-line
+               comment */
+            var quote = "say \"hi\"";
+            var accented = "café ${1 + 1} 日本";"#,
+        );
+        assert_eq!(
+            global(&interpreter, "escaped"),
+            Lit::Str("a\n\t\r\\\"\0b".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "unicode"),
+            Lit::Str("H\u{e9}\u{1F600}".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "quote"),
+            Lit::Str("say \"hi\"".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "accented"),
+            Lit::Str("café 2 日本".to_string())
+        );
+
+        // Only ASCII digits make up numbers, others are unexpected characters
+        for source in ["var x = ²;", "var x = 1٣;"] {
+            assert!(
+                Scanner::new(source.to_string()).scan_tokens().is_err(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_string_escapes() {
+        for source in [
+            r#""\q";"#,
+            r#""\u41";"#,
+            r#""\u{}";"#,
+            r#""\u{D800}";"#,
+            r#""\u{110000}";"#,
+            r#""\u{1234567}";"#,
+        ] {
+            assert!(
+                Scanner::new(source.to_string()).scan_tokens().is_err(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let interpreter = run_source(
+            r#"var a = 1;
+            var b = 2;
+            var total = "total: ${a + b} items";
+            var many = "${a}${b}, ${"nested ${a}"} ${{"k": [true, nil]}["k"]}";
+            var literal = "costs \${a} or $a";"#,
+        );
+        assert_eq!(
+            global(&interpreter, "total"),
+            Lit::Str("total: 3 items".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "many"),
+            Lit::Str("12, nested 1 [true, nil]".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "literal"),
+            Lit::Str("costs ${a} or $a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unterminated_interpolation() {
+        assert!(Scanner::new(r#""a ${1 + 2";"#.to_string())
+            .scan_tokens()
+            .is_err());
+        assert!(fails_to_parse(r#""a ${}";"#));
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let interpreter = run_source(
+            "var sum = 0;
+            for (var i = 0; i < 10; i = i + 1) {
+                if (i == 2) continue;
+                if (i == 5) break;
+                sum = sum + i;
+            }
+            var count = 0;
+            while (true) {
+                count = count + 1;
+                {
+                    if (count < 3) continue;
+                }
+                break;
+            }
+            var inner = 0;
+            for (var i = 0; i < 3; i = i + 1) {
+                for (var j = 0; j < 3; j = j + 1) {
+                    if (j == 1) break;
+                    inner = inner + 1;
+                }
+            }",
+        );
+        assert_eq!(global(&interpreter, "sum"), Lit::Num(8.0));
+        assert_eq!(global(&interpreter, "count"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "inner"), Lit::Num(3.0));
+    }
+
+    #[test]
+    fn test_break_outside_loop() {
+        assert!(!resolves(&parse_source("break;")));
+        assert!(!resolves(&parse_source("if (true) continue;")));
+        assert!(!resolves(&parse_source(
+            "while (true) { fun f() { break; } }"
+        )));
+    }
+
+    #[test]
+    fn test_modulo_power_and_floor_division() {
+        let interpreter = run_source(
+            "var mods = [7 % 3, -7 % 3, 7 % -3, -7 % -3, 6 % 3];
+            var floors = [7 ~/ 2, -7 ~/ 2, 7 ~/ -2, 6 ~/ 3];
+            var power = 2 ** 3 ** 2;
+            var negated = -2 ** 2;
+            var inverse = 2 ** -1;
+            var precedence = 1 + 2 * 3 ** 2 % 5;",
+        );
+        assert_eq!(
+            global(&interpreter, "mods").to_string(),
+            "[1, 2, -2, -1, 0]"
+        );
+        assert_eq!(global(&interpreter, "floors").to_string(), "[3, -4, -4, 2]");
+        assert_eq!(global(&interpreter, "power"), Lit::Num(512.0));
+        assert_eq!(global(&interpreter, "negated"), Lit::Num(-4.0));
+        assert_eq!(global(&interpreter, "inverse"), Lit::Num(0.5));
+        assert_eq!(global(&interpreter, "precedence"), Lit::Num(4.0));
+    }
+
+    #[test]
+    fn test_arithmetic_operator_type_errors() {
+        for source in ["1 % \"a\";", "\"a\" ** 2;", "nil ~/ 2;"] {
+            let statements = parse_source(source);
+            assert!(!Interpreter::new().interpret(&statements), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let interpreter = run_source(
+            "var both = true and false;
+            var first = nil and 1;
+            var second = 1 and 2;
+            var either = false or 3;",
+        );
+        assert_eq!(global(&interpreter, "both"), Lit::Bool(false));
+        assert_eq!(global(&interpreter, "first"), Lit::Nil);
+        assert_eq!(global(&interpreter, "second"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "either"), Lit::Num(3.0));
+    }
+
+    #[test]
+    fn test_conditional_and_coalesce() {
+        let interpreter = run_source(
+            "var calls = 0;
+            fun side() { calls = calls + 1; return calls; }
+            var picked = 1 < 2 ? \"yes\" : side();
+            var nested = false ? 1 : nil ? 2 : 3;
+            var assigned;
+            assigned = true ? 1 : 2;
+            var kept = false ?? side();
+            var replaced = nil ?? \"default\";
+            var chained = nil ?? nil ?? 4;
+            var low = nil ?? false ? \"truthy\" : \"falsy\";",
+        );
+        assert_eq!(global(&interpreter, "picked"), Lit::Str("yes".to_string()));
+        assert_eq!(global(&interpreter, "nested"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "assigned"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "kept"), Lit::Bool(false));
+        assert_eq!(
+            global(&interpreter, "replaced"),
+            Lit::Str("default".to_string())
+        );
+        assert_eq!(global(&interpreter, "chained"), Lit::Num(4.0));
+        assert_eq!(global(&interpreter, "low"), Lit::Str("falsy".to_string()));
+        assert_eq!(global(&interpreter, "calls"), Lit::Num(0.0));
+    }
+
+    #[test]
+    fn test_optional_chaining() {
+        let interpreter = run_source(
+            "class Box { init(v) { this.v = v; } get() { return this.v; } }
+            var calls = 0;
+            fun side() { calls = calls + 1; }
+            var none = nil;
+            var missing = none?.v;
+            var skipped = none?.(side());
+            var present = Box(1)?.v;
+            var called = Box(2).get?.();
+            var method = none?.get();
+            var nested = none?.v.w[0];
+            var inner = Box(nil).v?.w;",
+        );
+        assert_eq!(global(&interpreter, "missing"), Lit::Nil);
+        assert_eq!(global(&interpreter, "skipped"), Lit::Nil);
+        assert_eq!(global(&interpreter, "present"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "called"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "calls"), Lit::Num(0.0));
+        assert_eq!(global(&interpreter, "method"), Lit::Nil);
+        assert_eq!(global(&interpreter, "nested"), Lit::Nil);
+        assert_eq!(global(&interpreter, "inner"), Lit::Nil);
+
+        let statements = parse_source("var x = 1; x?.y;");
+        assert!(!Interpreter::new().interpret(&statements));
+        assert!(fails_to_parse("var a; a?.b = 1;"));
+        assert!(fails_to_parse("var a; a?.b.c = 1;"));
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let interpreter = run_source(
+            "var a = 1;
+            a += 2;
+            a *= 4;
+            a -= 2;
+            a /= 5;
+            var s = \"ab\";
+            s += \"c\";
+            class P {}
+            var p = P();
+            p.x = 1;
+            p.x += 4;
+            var calls = 0;
+            fun index() { calls = calls + 1; return 0; }
+            var list = [10];
+            list[index()] += 5;
+            var m = {\"k\": 1};
+            m[\"k\"] *= 3;
+            var result = (a += 1);",
+        );
+        assert_eq!(global(&interpreter, "a"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "result"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "s"), Lit::Str("abc".to_string()));
+        assert_eq!(global(&interpreter, "calls"), Lit::Num(1.0));
+        let source = "var x = p.x; var l = list[0]; var k = m[\"k\"];";
+        let mut interpreter = interpreter;
+        assert!(interpreter.interpret(&parse_source(source)));
+        assert_eq!(global(&interpreter, "x"), Lit::Num(5.0));
+        assert_eq!(global(&interpreter, "l"), Lit::Num(15.0));
+        assert_eq!(global(&interpreter, "k"), Lit::Num(3.0));
+    }
+
+    #[test]
+    fn test_increment_and_decrement() {
+        let interpreter = run_source(
+            "var i = 0;
+            var post = i++;
+            var pre = ++i;
+            var down = i--;
+            --i;
+            var list = [1];
+            list[0]++;
+            var first = list[0];
+            fun count() { var n = 0; for (var j = 0; j < 3; j++) n++; return n; }
+            var counted = count();",
+        );
+        assert_eq!(global(&interpreter, "post"), Lit::Num(0.0));
+        assert_eq!(global(&interpreter, "pre"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "down"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "i"), Lit::Num(0.0));
+        assert_eq!(global(&interpreter, "first"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "counted"), Lit::Num(3.0));
+
+        for source in ["1 += 2;", "(a) ++;", "++nil;", "var a; a + 1 -= 2;"] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+        let statements = parse_source("var s = \"a\"; s++;");
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_lambdas() {
+        let interpreter = run_source(
+            "fun apply(f, x) { return f(x); }
+            var doubled = apply((a) => a * 2, 4);
+            var summed = apply(fun (a) { var b = a + 1; return b; }, 1);
+            fun adder(n) { return (x) => x + n; }
+            var added = adder(10)(5);
+            var constant = (() => 7)();
+            var pair = ((a, b) => a - b)(5, 3);
+            var grouped = (doubled);
+            fun (x) { return x; };
+            var shown = \"${fun () {}}\";",
+        );
+        assert_eq!(global(&interpreter, "doubled"), Lit::Num(8.0));
+        assert_eq!(global(&interpreter, "summed"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "added"), Lit::Num(15.0));
+        assert_eq!(global(&interpreter, "constant"), Lit::Num(7.0));
+        assert_eq!(global(&interpreter, "pair"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "grouped"), Lit::Num(8.0));
+        assert_eq!(
+            global(&interpreter, "shown"),
+            Lit::Str("<fn lambda>".to_string())
+        );
+
+        for source in ["var f = (a, 1) => a;", "var f = (a) =>;"] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_try_catch_finally() {
+        let interpreter = run_source(
+            "var log = \"\";
+            try { throw \"boom\"; log += \"unreached\"; } catch (e) { log += e; }
+            var message;
+            var line;
+            try {
+                nil + 1;
+            } catch (e) {
+                message = e.message;
+                line = e.line;
+            }
+            var undefined;
+            try { missing; } catch (e) { undefined = e.message; }
+            var cleaned = false;
+            fun early() { try { return 1; } finally { cleaned = true; } }
+            var returned = early();
+            var nested;
+            try {
+                try { throw 1; } finally { log += \"!\"; }
+            } catch (e) { nested = e; }
+            var replaced;
+            try {
+                try { throw 1; } catch (e) { throw e + 1; }
+            } catch { replaced = true; }
+            var arity;
+            try { early(1); } catch (e) { arity = e.message; }",
+        );
+        assert_eq!(global(&interpreter, "log"), Lit::Str("boom!".to_string()));
+        assert_eq!(
+            global(&interpreter, "message"),
+            Lit::Str("Expected two numbers or two strings.".to_string())
+        );
+        assert_eq!(global(&interpreter, "line"), Lit::Num(6.0));
+        assert_eq!(
+            global(&interpreter, "undefined"),
+            Lit::Str("Undefined variable 'missing'.".to_string())
+        );
+        assert_eq!(global(&interpreter, "cleaned"), Lit::Bool(true));
+        assert_eq!(global(&interpreter, "returned"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "nested"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "replaced"), Lit::Bool(true));
+        assert!(matches!(global(&interpreter, "arity"), Lit::Str(_)));
+    }
+
+    #[test]
+    fn test_uncaught_throw() {
+        let statements = parse_source("var after = 1; throw \"up\"; after = 2;");
+        let mut interpreter = Interpreter::new();
+        assert!(!interpreter.interpret(&statements));
+        assert_eq!(global(&interpreter, "after"), Lit::Num(1.0));
+
+        let statements = parse_source("try { throw 1; } finally { print \"done\"; }");
+        assert!(!Interpreter::new().interpret(&statements));
+
+        // A rethrown runtime error still carries the original error to report
+        let statements = parse_source("try { nil + 1; } catch (e) { throw e; }");
+        assert!(resolves(&statements));
+        let error = Interpreter::new().execute(&statements[0]).unwrap_err();
+        let LoxResult::Throw {
+            value: Lit::Instance(instance),
+            ..
+        } = error
+        else {
+            panic!("expected a thrown instance");
+        };
+        let Some(LoxResult::RuntimeError { token, message }) = &instance.origin else {
+            panic!("expected the original runtime error");
+        };
+        assert_eq!(token.lexeme, "+");
+        assert_eq!(message, "Expected two numbers or two strings.");
+
+        assert!(fails_to_parse("try { }"));
+    }
+
+    #[test]
+    fn test_imports() {
+        let dir = std::env::temp_dir().join(format!("rlox_imports_{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        let files = [
+            ("main.lox", ""),
+            (
+                "lib/util.lox",
+                "var scale = 3;
+                fun triple(x) { return x * scale; }
+                import \"helper.lox\" as helper;",
+            ),
+            ("lib/helper.lox", "var name = \"helper\";"),
+            ("cycle_a.lox", "import \"cycle_b.lox\";"),
+            ("cycle_b.lox", "import \"cycle_a.lox\";"),
+        ];
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_script(&dir.join("main.lox"));
+        let statements = parse_source(
+            "var scale = 10;
+            import \"lib/util.lox\" as util;
+            import { triple } from \"lib/util.lox\";
+            import \"lib/../lib/util.lox\" as again;
+            var same = util == again;
+            var tripled = triple(2);
+            var helper = util.helper.name;
+            var shown = \"${util}\";",
+        );
+        assert!(resolves(&statements));
+        assert!(interpreter.interpret(&statements));
+        assert_eq!(global(&interpreter, "same"), Lit::Bool(true));
+        assert_eq!(global(&interpreter, "tripled"), Lit::Num(6.0));
+        assert_eq!(
+            global(&interpreter, "helper"),
+            Lit::Str("helper".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "shown"),
+            Lit::Str("<module lib/util.lox>".to_string())
+        );
+
+        for source in [
+            "import \"missing.lox\";",
+            "import \"cycle_a.lox\";",
+            "import \"main.lox\";",
+            "import { clock } from \"lib/util.lox\";",
+        ] {
+            let statements = parse_source(source);
+            assert!(!interpreter.interpret(&statements), "{}", source);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_constants() {
+        let interpreter = run_source(
+            "const limit = 10;
+            const list = [1];
+            push(list, 2);
+            fun scoped() { const inner = 1; var shadow = inner + limit; return shadow; }
+            var total = scoped();
+            var redeclared = 1;
+            var redeclared = 2;",
+        );
+        assert_eq!(global(&interpreter, "total"), Lit::Num(11.0));
+        assert_eq!(global(&interpreter, "redeclared"), Lit::Num(2.0));
+
+        for source in [
+            "const a = 1; a = 2;",
+            "const a = 1; var a = 2; a = 3;",
+            "const a = 1; const a = 2;",
+            "const a = 1; fun a() {}",
+            "const a = 1; var [a] = [2];",
+            "const a = 1; a += 2;",
+            "{ const a = 1; a++; }",
+            "fun f() { const a = 1; fun g() { a = 2; } }",
+        ] {
+            assert!(!resolves(&parse_source(source)), "{}", source);
+        }
+
+        // Only known to be constant once the declaration has run
+        let statements = parse_source("fun set() { a = 2; } const a = 1; set();");
+        assert!(resolves(&statements));
+        let mut interpreter = Interpreter::new();
+        assert!(!interpreter.interpret(&statements));
+        assert_eq!(global(&interpreter, "a"), Lit::Num(1.0));
+
+        // Separately resolved runs, like lines of the REPL, share their globals
+        let mut interpreter = Interpreter::new();
+        assert!(interpreter.interpret(&parse_source("const a = 1;")));
+        assert!(!interpreter.interpret(&parse_source("var a = 2;")));
+        assert!(!interpreter.interpret(&parse_source("var [a] = [2];")));
+        assert_eq!(global(&interpreter, "a"), Lit::Num(1.0));
+
+        assert!(fails_to_parse("const a;"));
+    }
+
+    #[test]
+    fn test_for_in_loops() {
+        let interpreter = run_source(
+            "var sum = 0;
+            for (x in [1, 2, 3]) sum += x;
+            var grown = [1];
+            for (x in grown) if (x < 4) push(grown, x + 1);
+            var joined = \"\";
+            for (k in {\"b\": 2, \"a\": 1}) joined += k;
+            for (c in \"xyz\") { if (c == \"y\") continue; joined += c; }
+            var range = 0;
+            for (i in 0..10) { if (i == 5) break; range += i; }
+            var closures = [];
+            for (i in 1..4) push(closures, () => i);
+            var captured = closures[0]() + closures[2]();
+            class Countdown {
+                init(n) { this.n = n; }
+                iter() { return Step(this.n); }
+            }
+            class Step {
+                init(n) { this.n = n; }
+                next() { if (this.n == 0) return nil; this.n--; return this.n + 1; }
+            }
+            var counted = \"\";
+            for (n in Countdown(3)) counted += \"${n}\";
+            for (n in Step(2)) counted += \"${n}\";
+            var shown = \"${2..5}\";",
+        );
+        assert_eq!(global(&interpreter, "sum"), Lit::Num(6.0));
+        assert_eq!(global(&interpreter, "joined"), Lit::Str("abxz".to_string()));
+        assert_eq!(global(&interpreter, "range"), Lit::Num(10.0));
+        assert_eq!(global(&interpreter, "captured"), Lit::Num(4.0));
+        assert_eq!(
+            global(&interpreter, "counted"),
+            Lit::Str("32121".to_string())
+        );
+        assert_eq!(global(&interpreter, "shown"), Lit::Str("2..5".to_string()));
+        match global(&interpreter, "grown") {
+            Lit::List(list) => assert_eq!(list.borrow().len(), 4),
+            other => panic!("Expected a list, got {}", other),
+        }
+
+        for source in ["for (x in 5) {}", "for (x in \"a\"..2) {}"] {
+            let statements = parse_source(source);
+            assert!(!Interpreter::new().interpret(&statements), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_generators() {
+        let interpreter = run_source(
+            "fun naturals() { var n = 0; while (true) { yield n; n++; } }
+            var gen = naturals();
+            var first = gen.next() + gen.next() + gen.next();
+            fun evens(source) {
+                for (n in source) {
+                    if (n > 8) return;
+                    if (n % 2 == 1) continue;
+                    yield n;
+                }
+            }
+            var collected = [];
+            for (n in evens(naturals())) push(collected, n);
+            fun pair(a, b) { yield a; { var c = a + b; yield c; } yield; }
+            var p = pair(1, 2);
+            var steps = [p.next(), p.next(), p.next(), p.done, p.next(), p.done];
+            var calls = 0;
+            fun lazy() { calls++; yield 1; calls++; }
+            var l = lazy();
+            var before = calls;
+            l.next();
+            var after = calls;
+            fun counted() { for (var i = 0; i < 3; i++) yield i; }
+            var sum = 0;
+            for (i in counted()) sum += i;
+            fun holes() { yield 1; yield nil; yield 3; }
+            var seen = [];
+            for (x in holes()) push(seen, x);
+            var shown = \"${counted()}\";",
+        );
+        assert_eq!(global(&interpreter, "first"), Lit::Num(3.0));
+        assert_eq!(
+            global(&interpreter, "collected").to_string(),
+            "[0, 2, 4, 6, 8]"
+        );
+        assert_eq!(
+            global(&interpreter, "steps").to_string(),
+            "[1, 3, nil, false, nil, true]"
+        );
+        assert_eq!(global(&interpreter, "before"), Lit::Num(0.0));
+        assert_eq!(global(&interpreter, "after"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "sum"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "seen").to_string(), "[1, nil, 3]");
+        assert_eq!(
+            global(&interpreter, "shown"),
+            Lit::Str("<generator counted>".to_string())
+        );
+
+        for source in [
+            "yield 1;",
+            "class A { init() { yield 1; } }",
+            "fun f() { try { yield 1; } catch {} }",
+        ] {
+            assert!(!resolves(&parse_source(source)), "{}", source);
+        }
+
+        let statements =
+            parse_source("fun f() { yield 1; nil + 1; } var g = f(); g.next(); g.next();");
+        assert!(!Interpreter::new().interpret(&statements));
+        let statements = parse_source("var g; fun f() { g.next(); yield 1; } g = f(); g.next();");
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_match() {
+        let interpreter = run_source(
+            "fun describe(x) {
+                match (x) {
+                    0 => return \"zero\";
+                    1 | 2 | 3 => return \"small\";
+                    -5..0 => return \"negative\";
+                    \"hi\" => return \"greeting\";
+                    [] => return \"empty\";
+                    [first, ...rest] if len(rest) > 1 => return \"long ${first}\";
+                    [a, b] => return \"pair ${a + b}\";
+                    {\"kind\": \"dog\", name} => return \"dog ${name}\";
+                    n if n == true => return \"true\";
+                    _ => return \"other\";
+                }
+            }
+            var results = [
+                describe(0), describe(2), describe(-3), describe(\"hi\"), describe([]),
+                describe([7, 8, 9]), describe([1, 2]), describe({\"kind\": \"dog\", \"name\": \"rex\"}),
+                describe(true), describe(10)
+            ];
+            var last;
+            for (i in 0..3) {
+                match (i) {
+                    0 => continue;
+                    n => { last = n; }
+                }
+            }
+            fun kinds(xs) { for (x in xs) match (x) { 0 => yield \"zero\"; _ => yield \"other\"; } }
+            var seen = [];
+            for (k in kinds([0, 1])) push(seen, k);",
+        );
+        assert_eq!(
+            global(&interpreter, "results").to_string(),
+            "[\"zero\", \"small\", \"negative\", \"greeting\", \"empty\", \"long 7\", \
+             \"pair 3\", \"dog rex\", \"true\", \"other\"]"
+        );
+        assert_eq!(global(&interpreter, "last"), Lit::Num(2.0));
+        assert_eq!(
+            global(&interpreter, "seen").to_string(),
+            "[\"zero\", \"other\"]"
+        );
+
+        let statements = parse_source("match (3) { 1 => print 1; }");
+        assert!(!Interpreter::new().interpret(&statements));
+        assert!(!resolves(&parse_source("match (1) { a | 2 => print a; }")));
+        assert!(!resolves(&parse_source("match (1) { [a, a] => print a; }")));
+    }
+
+    #[test]
+    fn test_default_rest_and_named_arguments() {
+        let interpreter = run_source(
+            "fun f(a, b = a * 2, ...rest) { return [a, b, rest]; }
+            var positional = f(1, 2, 3, 4);
+            var defaulted = f(1);
+            var named = f(b: 5, a: 3);
+            var explicit = f(1, nil);
+            var spread = ((...xs) => len(xs))(1, 2, 3);
+            class Point { init(x = 0, y = 0) { this.x = x; this.y = y; } }
+            var point = Point(y: 4);
+            var cleared = Point(nil);
+            var pushed = [1];
+            push(value: 2, list: pushed);
+            fun message(call) { try { call(); } catch (e) { return e.message; } }
+            var missing = message(() => f());
+            var unexpected = message(() => f(1, c: 2));
+            var twice = message(() => f(1, a: 2));
+            var extra = message(() => len(1, 2));
+            var extras = message(() => Point(1, 2, 3));",
+        );
+        assert_eq!(
+            global(&interpreter, "positional").to_string(),
+            "[1, 2, [3, 4]]"
+        );
+        assert_eq!(global(&interpreter, "defaulted").to_string(), "[1, 2, []]");
+        assert_eq!(global(&interpreter, "named").to_string(), "[3, 5, []]");
+        assert_eq!(global(&interpreter, "explicit").to_string(), "[1, nil, []]");
+        assert_eq!(global(&interpreter, "spread"), Lit::Num(3.0));
+        assert_eq!(global(&interpreter, "pushed").to_string(), "[1, 2]");
+        let Lit::Instance(point) = global(&interpreter, "point") else {
+            panic!("expected an instance");
+        };
+        let x = Token::new(TokenType::Identifier, "x", None, 0);
+        assert_eq!(point.get(&x).unwrap(), Lit::Num(0.0));
+        let Lit::Instance(cleared) = global(&interpreter, "cleared") else {
+            panic!("expected an instance");
+        };
+        assert_eq!(cleared.get(&x).unwrap(), Lit::Nil);
+        for (name, message) in [
+            ("missing", "Missing argument for parameter 'a'."),
+            ("unexpected", "Unexpected argument 'c'."),
+            ("twice", "Parameter 'a' was given more than once."),
+            ("extra", "Expected at most 1 argument, but got 2."),
+            ("extras", "Expected at most 2 arguments, but got 3."),
+        ] {
+            assert_eq!(global(&interpreter, name), Lit::Str(message.to_string()));
+        }
+
+        for source in ["fun f(a = 1, b) {}", "fun f(...rest, a) {}", "f(a: 1, 2);"] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let interpreter = run_source(
+            "var [a, b, ...rest] = [1, 2, 3, 4];
+            var {name, \"tags\": [first, _]} = {\"name\": \"rex\", \"tags\": [\"dog\", \"good\"]};
+            var [x, [y], {z}] = [1];
+            [a, b] = [b, a];
+            {name, first} = {\"name\": \"tom\"};
+            fun area([w, h], {scale}) { return w * h * scale; }
+            var result = area([2, 3], {\"scale\": 2});
+            fun swapped() {
+                var [m, n] = [5, 6];
+                [m, n] = [n, m];
+                return [m, n];
+            }
+            var local = swapped();
+            var [_, snake_case] = [1, 2];
+            fun second(_, b) { return b; }
+            var ignored = second(1, 2);
+            var message;
+            try { var [bad] = 1; } catch (e) { message = e.message; }",
+        );
+        assert_eq!(global(&interpreter, "a"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "b"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "rest").to_string(), "[3, 4]");
+        assert_eq!(global(&interpreter, "name"), Lit::Str("tom".to_string()));
+        assert_eq!(global(&interpreter, "first"), Lit::Nil);
+        assert_eq!(global(&interpreter, "x"), Lit::Num(1.0));
+        assert_eq!(global(&interpreter, "y"), Lit::Nil);
+        assert_eq!(global(&interpreter, "z"), Lit::Nil);
+        assert_eq!(global(&interpreter, "result"), Lit::Num(12.0));
+        assert_eq!(global(&interpreter, "local").to_string(), "[6, 5]");
+        assert_eq!(global(&interpreter, "snake_case"), Lit::Num(2.0));
+        assert_eq!(global(&interpreter, "ignored"), Lit::Num(2.0));
+        assert_eq!(
+            global(&interpreter, "message"),
+            Lit::Str("Can only destructure a list with '[...]', not 1.".to_string())
+        );
+
+        assert!(!resolves(&parse_source("const c = 1; [c] = [2];")));
+        assert!(!resolves(&parse_source("fun f([a, a]) {}")));
+        for source in ["var [1] = [1];", "var [a];", "var [a | b] = [1];"] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_enums() {
+        let interpreter = run_source(
+            "enum Shape { Circle(r), Rect(w, h), Empty }
+            fun area(shape) {
+                match (shape) {
+                    Shape.Circle(r) => return 3 * r * r;
+                    Shape.Rect(w, h) if w == h => return \"square\";
+                    Shape.Rect(w, h) => return w * h;
+                    Shape.Empty => return 0;
+                }
+            }
+            var areas = [area(Shape.Circle(2)), area(Shape.Rect(h: 5, w: 2)), area(Shape.Rect(1, 1)), area(Shape.Empty)];
+            var shown = \"${Shape.Rect(1, 2)} ${Shape.Empty}\";
+            var equal = Shape.Circle([1]) == Shape.Circle([1]) and Shape.Empty == Shape.Empty;
+            var different = Shape.Circle(1) == Shape.Circle(2) or Shape.Empty == nil;
+            var radius = Shape.Circle(4).r;
+            var message;
+            try { Shape.Circle(); } catch (e) { message = e.message; }
+            fun isRound(shape) { match (shape) { Shape.Circle(_) => return true; _ => return false; } }
+            var before = Shape.Circle(1);
+            enum Shape { Circle(r) }
+            var identity = [isRound(before), isRound(Shape.Circle(1))];
+            fun shadowed() { class Shape { static Square() { return 1; } } return Shape.Square(); }
+            var square = shadowed();",
+        );
+        assert_eq!(
+            global(&interpreter, "areas").to_string(),
+            "[12, 10, \"square\", 0]"
+        );
+        assert_eq!(
+            global(&interpreter, "shown"),
+            Lit::Str("Shape.Rect(1, 2) Shape.Empty".to_string())
+        );
+        assert_eq!(global(&interpreter, "equal"), Lit::Bool(true));
+        assert_eq!(global(&interpreter, "different"), Lit::Bool(false));
+        assert_eq!(global(&interpreter, "radius"), Lit::Num(4.0));
+        assert_eq!(
+            global(&interpreter, "message"),
+            Lit::Str("Missing argument for parameter 'r'.".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "identity").to_string(),
+            "[false, true]"
+        );
+        assert_eq!(global(&interpreter, "square"), Lit::Num(1.0));
+        assert!(resolves(&parse_source(
+            "enum S { A } { enum S { B } print S.B; } var S = 1; print S.C;"
+        )));
+
+        assert!(!resolves(&parse_source("enum S { A } print S.B;")));
+        assert!(!resolves(&parse_source(
+            "enum S { A(x) } match (1) { S.A(a, b) => print a; }"
+        )));
+        for source in ["enum S { A, A }", "enum S { A(x, x) }", "enum { A }"] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_traits() {
+        let interpreter = run_source(
+            "trait Named { describe() { return \"I am \" + this.name; } }
+            trait Greets { greet(other) { return \"Hi \" + other.name + \", \" + this.describe(); } }
+            trait Loud { describe() { return \"LOUD\"; } }
+            class Base { init(name) { this.name = name; } }
+            class Person < Base with Named, Greets {}
+            class Settled with Named, Loud { describe() { return \"own\"; } }
+            var greeting = Person(\"ann\").greet(Person(\"bob\"));
+            var bound = Person(\"cy\").describe;
+            var described = bound();
+            var settled = Settled().describe();
+            var message;
+            try { class Clash with Named, Loud {} } catch (e) { message = e.message; }",
+        );
+        assert_eq!(
+            global(&interpreter, "greeting"),
+            Lit::Str("Hi bob, I am ann".to_string())
+        );
+        assert_eq!(
+            global(&interpreter, "described"),
+            Lit::Str("I am cy".to_string())
+        );
+        assert_eq!(global(&interpreter, "settled"), Lit::Str("own".to_string()));
+        assert_eq!(
+            global(&interpreter, "message"),
+            Lit::Str("Traits 'Named' and 'Loud' both define a method 'describe'.".to_string())
+        );
+
+        assert!(!resolves(&parse_source(
+            "trait T { f() { return super.f(); } }"
+        )));
+        let statements = parse_source("var x = 1; class C with x {}");
+        assert!(resolves(&statements));
+        assert!(!Interpreter::new().interpret(&statements));
+    }
+
+    #[test]
+    fn test_static_methods_getters_and_setters() {
+        let interpreter = run_source(
+            "class Rect {
+                init(w, h) { this.w = w; this.h = h; }
+                area { return this.w * this.h; }
+                side { return this.w; }
+                set side(value) { this.w = value; this.h = value; }
+                static square(n) { return Rect(n, n); }
+            }
+            class Box < Rect { area { return super.area + 1; } }
+            var rect = Rect(2, 3);
+            var area = rect.area;
+            rect.side = 4;
+            var squared = rect.area;
+            rect.side += 1;
+            var side = rect.side;
+            var inherited = Box.square(3).area;
+            var padded = Box(2, 2).area;
+            var message;
+            try { rect.area = 1; } catch (e) { message = e.message; }",
+        );
+        assert_eq!(global(&interpreter, "area"), Lit::Num(6.0));
+        assert_eq!(global(&interpreter, "squared"), Lit::Num(16.0));
+        assert_eq!(global(&interpreter, "side"), Lit::Num(5.0));
+        assert_eq!(global(&interpreter, "inherited"), Lit::Num(9.0));
+        assert_eq!(global(&interpreter, "padded"), Lit::Num(5.0));
+        assert_eq!(
+            global(&interpreter, "message"),
+            Lit::Str("Property 'area' has a getter but no setter.".to_string())
+        );
+
+        assert!(!resolves(&parse_source(
+            "class A { static f() { return this; } }"
+        )));
+        assert!(!resolves(&parse_source(
+            "class A < B { static f() { return super.f(); } }"
+        )));
+        let statements = parse_source("class A {} A.missing();");
+        assert!(!Interpreter::new().interpret(&statements));
+        for source in [
+            "class A { set f() {} }",
+            "class A { set f(a, b) {} }",
+            "class A { f { return 1; } f() {} }",
+        ] {
+            assert!(fails_to_parse(source), "{}", source);
+        }
+    }
 }
@@ -1,19 +1,88 @@
-use std::{fmt::Display, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
 use crate::{
-    interpreter::Interpreter, lox_callable::LoxCallable, lox_function::LoxFunction,
-    lox_native::LoxNative, LoxResult,
+    error::LoxResult,
+    lox_class::LoxClass,
+    lox_enum::{LoxEnum, LoxVariant},
+    lox_function::LoxFunction,
+    lox_generator::LoxGenerator,
+    lox_instance::LoxInstance,
+    lox_map::MapKey,
+    lox_module::LoxModule,
+    lox_native::LoxNative,
+    lox_trait::LoxTrait,
+    token::Token,
 };
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Lit {
     Num(f64),
     Str(String),
     Bool(bool),
     Func(Rc<LoxFunction>),
     Native(Rc<LoxNative>),
+    Class(Rc<LoxClass>),
+    Trait(Rc<LoxTrait>),
+    Instance(Rc<LoxInstance>),
+    Enum(Rc<LoxEnum>),
+    Variant(Rc<LoxVariant>),
+    Generator(Rc<LoxGenerator>),
+    List(Rc<RefCell<Vec<Lit>>>),
+    Map(Rc<RefCell<HashMap<MapKey, Lit>>>),
+    Module(Rc<LoxModule>),
+    /// The numbers from the start up to, but not including, the end
+    Range(f64, f64),
     Nil,
 }
 
+impl PartialEq for Lit {
+    /// Lists and maps are compared element by element, except that one is always equal to itself
+    /// without looking inside, so that a list containing itself doesn't recurse forever
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Lit::Num(a), Lit::Num(b)) => a == b,
+            (Lit::Str(a), Lit::Str(b)) => a == b,
+            (Lit::Bool(a), Lit::Bool(b)) => a == b,
+            (Lit::Func(a), Lit::Func(b)) => a == b,
+            (Lit::Native(a), Lit::Native(b)) => a == b,
+            (Lit::Class(a), Lit::Class(b)) => a == b,
+            (Lit::Trait(a), Lit::Trait(b)) => a == b,
+            (Lit::Instance(a), Lit::Instance(b)) => a == b,
+            (Lit::Enum(a), Lit::Enum(b)) => a == b,
+            (Lit::Variant(a), Lit::Variant(b)) => a == b,
+            (Lit::Generator(a), Lit::Generator(b)) => a == b,
+            (Lit::List(a), Lit::List(b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Lit::Map(a), Lit::Map(b)) => Rc::ptr_eq(a, b) || *a.borrow() == *b.borrow(),
+            (Lit::Module(a), Lit::Module(b)) => a == b,
+            (Lit::Range(a_start, a_end), Lit::Range(b_start, b_end)) => {
+                a_start == b_start && a_end == b_end
+            }
+            (Lit::Nil, Lit::Nil) => true,
+            _ => false,
+        }
+    }
+}
+
+thread_local! {
+    /// The lists and maps that are being displayed, so that one that contains itself is shown as
+    /// `[...]` or `{...}` the second time around
+    static DISPLAYING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Displays a list or map with `display`, unless it is already being displayed further up
+fn display_once(
+    container: *const (),
+    placeholder: &str,
+    display: impl FnOnce() -> String,
+) -> String {
+    if DISPLAYING.with(|displaying| displaying.borrow().contains(&container)) {
+        return placeholder.to_string();
+    }
+    DISPLAYING.with(|displaying| displaying.borrow_mut().push(container));
+    let shown = display();
+    DISPLAYING.with(|displaying| displaying.borrow_mut().pop());
+    shown
+}
+
 impl Display for Lit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -32,12 +101,54 @@ impl Display for Lit {
                 Lit::Nil => {
                     String::from("nil")
                 }
-                Lit::Func(_) => {
-                    String::from("{func}")
+                Lit::Func(func) => {
+                    func.to_string()
                 }
                 Lit::Native(_) => {
                     String::from("{n}")
                 }
+                Lit::Class(class) => {
+                    class.to_string()
+                }
+                Lit::Trait(lox_trait) => {
+                    lox_trait.to_string()
+                }
+                Lit::Instance(instance) => {
+                    instance.to_string()
+                }
+                Lit::Enum(lox_enum) => {
+                    lox_enum.to_string()
+                }
+                Lit::Variant(variant) => {
+                    variant.to_string()
+                }
+                Lit::Generator(generator) => {
+                    generator.to_string()
+                }
+                Lit::Module(module) => {
+                    module.to_string()
+                }
+                Lit::Range(start, end) => {
+                    format!("{}..{}", start, end)
+                }
+                Lit::List(list) => display_once(Rc::as_ptr(list).cast(), "[...]", || {
+                    let elements = list
+                        .borrow()
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<String>>();
+                    format!("[{}]", elements.join(", "))
+                }),
+                Lit::Map(map) => display_once(Rc::as_ptr(map).cast(), "{...}", || {
+                    let map = map.borrow();
+                    let mut keys = map.keys().collect::<Vec<&MapKey>>();
+                    keys.sort();
+                    let entries = keys
+                        .into_iter()
+                        .map(|k| format!("{}: {}", k.to_lit(), map[k]))
+                        .collect::<Vec<String>>();
+                    format!("{{{}}}", entries.join(", "))
+                }),
             }
         )
     }
@@ -47,6 +158,26 @@ impl Lit {
     pub fn is_truthy(&self) -> bool {
         !matches!(self, Lit::Bool(false) | Lit::Nil)
     }
+
+    /// Converts the value to an index in `0..len`, reporting errors at `token`
+    pub fn to_index(&self, token: &Token, len: usize) -> Result<usize, LoxResult> {
+        match self {
+            Lit::Num(n) if n.fract() == 0.0 => {
+                if *n < 0.0 || *n >= len as f64 {
+                    Err(LoxResult::runtime_error(
+                        token.clone(),
+                        &format!("Index {} out of range for length {}.", n, len),
+                    ))
+                } else {
+                    Ok(*n as usize)
+                }
+            }
+            _ => Err(LoxResult::runtime_error(
+                token.clone(),
+                "Index must be an integer.",
+            )),
+        }
+    }
 }
 
 impl From<Lit> for bool {
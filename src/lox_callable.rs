@@ -1,6 +1,30 @@
-use crate::{error::LoxResult, interpreter::Interpreter, lit::Lit};
+use crate::{
+    error::LoxResult, interpreter::Interpreter, lit::Lit, signature::Signature, token::Token,
+};
 
 pub trait LoxCallable {
-    fn call(&self, _interp: &Interpreter, arguments: Vec<Lit>) -> Result<Lit, LoxResult>;
-    fn arity(&self) -> usize;
+    /// `paren` is the closing parenthesis of the call, runtime errors are reported at it.
+    /// `arguments` have already been bound to the parameters of the signature.
+    fn call(
+        &self,
+        interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult>;
+    fn signature(&self) -> Signature;
+
+    /// Calls with the arguments exactly as `Signature::bind` left them, where `None` is an optional
+    /// parameter that wasn't passed. Only functions with defaults need to tell that apart from nil.
+    fn call_bound(
+        &self,
+        interp: &mut Interpreter,
+        arguments: Vec<Option<Lit>>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| argument.unwrap_or(Lit::Nil))
+            .collect();
+        self.call(interp, arguments, paren)
+    }
 }
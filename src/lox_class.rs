@@ -0,0 +1,130 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lit::Lit;
+use crate::lox_callable::LoxCallable;
+use crate::lox_function::LoxFunction;
+use crate::lox_instance::LoxInstance;
+use crate::signature::Signature;
+use crate::token::Token;
+
+/// The functions that a class declares, by name
+#[derive(Default)]
+pub struct Members {
+    pub methods: HashMap<String, Rc<LoxFunction>>,
+    /// Run without arguments when the property is read
+    pub getters: HashMap<String, Rc<LoxFunction>>,
+    /// Run with the value when the property is assigned to
+    pub setters: HashMap<String, Rc<LoxFunction>>,
+    /// Called on the class itself, so they have no `this`
+    pub statics: HashMap<String, Rc<LoxFunction>>,
+}
+
+pub struct LoxClass {
+    pub name: String,
+    superclass: Option<Rc<LoxClass>>,
+    members: Members,
+    /// Calling a class needs an owning reference to it for the new instance
+    this: Weak<LoxClass>,
+}
+
+impl LoxClass {
+    pub fn new(name: &str, superclass: Option<Rc<LoxClass>>, members: Members) -> Rc<Self> {
+        Rc::new_cyclic(|this| Self {
+            name: name.to_string(),
+            superclass,
+            members,
+            this: this.clone(),
+        })
+    }
+
+    /// Looks the method up on this class first, then walks up the superclass chain
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find(name, |members| &members.methods)
+    }
+
+    pub fn find_getter(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find(name, |members| &members.getters)
+    }
+
+    pub fn find_setter(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find(name, |members| &members.setters)
+    }
+
+    pub fn find_static(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.find(name, |members| &members.statics)
+    }
+
+    fn find(
+        &self,
+        name: &str,
+        table: fn(&Members) -> &HashMap<String, Rc<LoxFunction>>,
+    ) -> Option<Rc<LoxFunction>> {
+        if let Some(function) = table(&self.members).get(name) {
+            return Some(Rc::clone(function));
+        }
+
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find(name, table))
+    }
+}
+
+impl PartialEq for LoxClass {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Class {}>", self.name)
+    }
+}
+
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl LoxCallable for LoxClass {
+    fn call(
+        &self,
+        interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        self.call_bound(interp, arguments.into_iter().map(Some).collect(), paren)
+    }
+
+    /// Hands the arguments to `init` as they were bound, so that its defaults still apply
+    fn call_bound(
+        &self,
+        interp: &mut Interpreter,
+        arguments: Vec<Option<Lit>>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let class = self
+            .this
+            .upgrade()
+            .ok_or_else(|| LoxResult::system_error("Called a class that no longer exists."))?;
+        let instance = Rc::new(LoxInstance::new(class));
+
+        if let Some(initializer) = self.find_method("init") {
+            initializer
+                .bind(Rc::clone(&instance))
+                .call_bound(interp, arguments, paren)?;
+        }
+
+        Ok(Lit::Instance(instance))
+    }
+
+    fn signature(&self) -> Signature {
+        self.find_method("init")
+            .map_or_else(|| Signature::new(&[]), |init| init.signature())
+    }
+}
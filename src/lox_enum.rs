@@ -0,0 +1,173 @@
+use core::fmt;
+use std::rc::Rc;
+
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lit::Lit;
+use crate::lox_callable::LoxCallable;
+use crate::lox_native::LoxNative;
+use crate::signature::Signature;
+use crate::token::Token;
+
+/// One `Name(field, ...)` variant of an enum declaration, the fields are left out for a variant
+/// without a payload
+pub struct Variant {
+    pub name: Token,
+    pub fields: Vec<Token>,
+}
+
+/// A variant of a declared enum, shared by all of its values
+pub struct VariantKind {
+    pub enum_name: String,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+pub struct LoxEnum {
+    pub name: String,
+    variants: Vec<Rc<VariantKind>>,
+}
+
+impl LoxEnum {
+    pub fn new(name: &str, variants: &[Variant]) -> Self {
+        let variants = variants
+            .iter()
+            .map(|variant| {
+                Rc::new(VariantKind {
+                    enum_name: name.to_string(),
+                    name: variant.name.lexeme.clone(),
+                    fields: variant.fields.iter().map(|f| f.lexeme.clone()).collect(),
+                })
+            })
+            .collect();
+        Self {
+            name: name.to_string(),
+            variants,
+        }
+    }
+
+    /// Variants without a payload are values themselves, the others are constructors for them
+    pub fn get(&self, name: &Token) -> Result<Lit, LoxResult> {
+        let Some(kind) = self.variants.iter().find(|kind| kind.name == name.lexeme) else {
+            return Err(LoxResult::runtime_error(
+                name.clone(),
+                &format!("Enum '{}' has no variant '{}'.", self.name, name.lexeme),
+            ));
+        };
+
+        if kind.fields.is_empty() {
+            Ok(Lit::Variant(Rc::new(LoxVariant {
+                kind: Rc::clone(kind),
+                values: Vec::new(),
+            })))
+        } else {
+            Ok(Lit::Native(Rc::new(LoxNative {
+                func: Rc::new(VariantConstructor {
+                    kind: Rc::clone(kind),
+                }),
+            })))
+        }
+    }
+}
+
+impl PartialEq for LoxEnum {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Enum {}>", self.name)
+    }
+}
+
+impl fmt::Display for LoxEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A value of an enum, tagged with its variant
+pub struct LoxVariant {
+    pub kind: Rc<VariantKind>,
+    pub values: Vec<Lit>,
+}
+
+impl LoxVariant {
+    /// Whether this is the variant `name` of `lox_enum` itself, rather than of another enum that
+    /// happens to have the same name
+    pub fn is(&self, lox_enum: &LoxEnum, name: &str) -> bool {
+        self.kind.name == name
+            && lox_enum
+                .variants
+                .iter()
+                .any(|kind| Rc::ptr_eq(kind, &self.kind))
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Lit, LoxResult> {
+        match self
+            .kind
+            .fields
+            .iter()
+            .position(|field| *field == name.lexeme)
+        {
+            Some(i) => Ok(self.values[i].clone()),
+            None => Err(LoxResult::runtime_error(
+                name.clone(),
+                &format!(
+                    "Variant '{}.{}' has no field '{}'.",
+                    self.kind.enum_name, self.kind.name, name.lexeme
+                ),
+            )),
+        }
+    }
+}
+
+impl PartialEq for LoxVariant {
+    /// Values are equal if they are the same variant of the same enum with equal payloads
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.kind, &other.kind) && self.values == other.values
+    }
+}
+
+impl fmt::Debug for LoxVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Variant {}.{}>", self.kind.enum_name, self.kind.name)
+    }
+}
+
+impl fmt::Display for LoxVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.kind.enum_name, self.kind.name)?;
+        if !self.values.is_empty() {
+            let values: Vec<String> = self.values.iter().map(Lit::to_string).collect();
+            write!(f, "({})", values.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates values of a variant with a payload, like `Shape.Circle(2)`
+pub struct VariantConstructor {
+    kind: Rc<VariantKind>,
+}
+
+impl LoxCallable for VariantConstructor {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        _paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        Ok(Lit::Variant(Rc::new(LoxVariant {
+            kind: Rc::clone(&self.kind),
+            values: arguments,
+        })))
+    }
+
+    fn signature(&self) -> Signature {
+        let fields: Vec<&str> = self.kind.fields.iter().map(String::as_str).collect();
+        Signature::new(&fields)
+    }
+}
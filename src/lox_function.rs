@@ -1,17 +1,169 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::error::LoxResult;
 use crate::interpreter::Interpreter;
+use crate::lit::Lit;
 use crate::lox_callable::LoxCallable;
+use crate::lox_generator::{contains_yield, LoxGenerator};
+use crate::lox_instance::LoxInstance;
+use crate::signature::{Parameter, Signature};
+use crate::stmt::{FunctionStmt, Stmt};
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+pub struct LoxFunction {
+    name: Token,
+    params: Rc<Vec<Parameter>>,
+    rest: Option<Token>,
+    body: Rc<Vec<Stmt>>,
+    closure: Rc<RefCell<Environment>>,
+    /// The global scope of the module the function was declared in
+    globals: Rc<RefCell<Environment>>,
+    is_initializer: bool,
+    /// Functions with a `yield` in their body return a generator instead of running it
+    is_generator: bool,
+}
+
+impl LoxFunction {
+    pub fn new(
+        declaration: &FunctionStmt,
+        closure: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        Self {
+            name: declaration.name.clone(),
+            params: Rc::clone(&declaration.params),
+            rest: declaration.rest.clone(),
+            body: Rc::clone(&declaration.body),
+            closure,
+            globals,
+            is_initializer,
+            is_generator: declaration.body.iter().any(contains_yield),
+        }
+    }
+
+    /// Creates a copy of this method whose closure has `this` bound to `instance`
+    pub fn bind(&self, instance: Rc<LoxInstance>) -> Self {
+        let mut environment = Environment::new_with_enclosing(Rc::clone(&self.closure));
+        environment.bind("this", Lit::Instance(instance));
+        Self {
+            name: self.name.clone(),
+            params: Rc::clone(&self.params),
+            rest: self.rest.clone(),
+            body: Rc::clone(&self.body),
+            closure: Rc::new(RefCell::new(environment)),
+            globals: Rc::clone(&self.globals),
+            is_initializer: self.is_initializer,
+            is_generator: self.is_generator,
+        }
+    }
+
+    fn this(&self) -> Result<Lit, LoxResult> {
+        let this = Token::new(TokenType::This, "this", None, self.name.line);
+        self.closure.borrow().get_at(0, &this)
+    }
+}
+
+impl PartialEq for LoxFunction {
+    /// Two functions are only equal if they come from the same declaration and closure
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && Rc::ptr_eq(&self.closure, &other.closure)
+    }
+}
+
+impl fmt::Debug for LoxFunction {
+    // The closure may contain this very function, so don't try to print it
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Function {}>", self.name.lexeme)
+    }
+}
+
+impl fmt::Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}>", self.name.lexeme)
+    }
+}
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct LoxFunction {}
 impl LoxCallable for LoxFunction {
     fn call(
         &self,
-        _interp: &Interpreter,
-        arguments: Vec<crate::lit::Lit>,
-    ) -> Result<crate::lit::Lit, crate::error::LoxResult> {
-        todo!()
+        interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        self.call_bound(interp, arguments.into_iter().map(Some).collect(), paren)
+    }
+
+    /// A parameter that was left out gets its default value, which is evaluated on every call and
+    /// can use the parameters before it. Passing nil explicitly doesn't count as leaving it out.
+    fn call_bound(
+        &self,
+        interp: &mut Interpreter,
+        arguments: Vec<Option<Lit>>,
+        _paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        // Unresolved variables are globals of the module the function comes from, not the caller's
+        let globals = std::mem::replace(&mut interp.globals, Rc::clone(&self.globals));
+        let result = self.run(interp, arguments);
+        interp.globals = globals;
+        result
+    }
+
+    fn signature(&self) -> Signature {
+        Signature {
+            params: self.params.iter().map(|p| p.name.lexeme.clone()).collect(),
+            required: self.params.iter().filter(|p| p.default.is_none()).count(),
+            rest: self.rest.as_ref().map(|rest| rest.lexeme.clone()),
+        }
     }
-    fn arity(&self) -> usize {
-        todo!()
+}
+
+impl LoxFunction {
+    fn run(&self, interp: &mut Interpreter, arguments: Vec<Option<Lit>>) -> Result<Lit, LoxResult> {
+        let environment = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &self.closure,
+        ))));
+        let mut arguments = arguments.into_iter();
+        for param in self.params.iter() {
+            let value = match (arguments.next().flatten(), &param.default) {
+                (None, Some(default)) => interp.evaluate_in(default, Rc::clone(&environment))?,
+                (value, _) => value.unwrap_or(Lit::Nil),
+            };
+            match &param.pattern {
+                Some(pattern) => {
+                    pattern.destructure(Some(&value), &mut environment.borrow_mut(), &param.name)?
+                }
+                None => environment.borrow_mut().bind(&param.name.lexeme, value),
+            }
+        }
+        if let Some(rest) = &self.rest {
+            let value = arguments.next().flatten().unwrap_or(Lit::Nil);
+            environment.borrow_mut().bind(&rest.lexeme, value);
+        }
+
+        if self.is_generator {
+            return Ok(Lit::Generator(Rc::new(LoxGenerator::new(
+                &self.name.lexeme,
+                Rc::clone(&self.body),
+                environment,
+                Rc::clone(&self.globals),
+            ))));
+        }
+
+        let result = self
+            .body
+            .iter()
+            .try_for_each(|stmt| interp.execute_in(stmt, Rc::clone(&environment)));
+        match result {
+            // An initializer always returns `this`, even when returning early
+            Err(LoxResult::ReturnValue { .. }) | Ok(()) if self.is_initializer => self.this(),
+            Err(LoxResult::ReturnValue { value }) => Ok(value),
+            Err(e) => Err(e),
+            Ok(()) => Ok(Lit::Nil),
+        }
     }
 }
@@ -0,0 +1,367 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lit::Lit;
+use crate::lox_callable::LoxCallable;
+use crate::lox_iterator::LoxIterator;
+use crate::signature::Signature;
+use crate::stmt::*;
+use crate::token::Token;
+
+/// Whether there is a `yield` somewhere in `stmt`, not counting nested functions
+pub fn contains_yield(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Yield(_) => true,
+        Stmt::Block(block) => block.statements.iter().any(contains_yield),
+        Stmt::If(stmt) => {
+            contains_yield(&stmt.then_branch)
+                || stmt.else_branch.as_deref().is_some_and(contains_yield)
+        }
+        Stmt::While(stmt) => contains_yield(&stmt.body),
+        Stmt::ForIn(stmt) => contains_yield(&stmt.body),
+        Stmt::Match(stmt) => stmt.arms.iter().any(|arm| contains_yield(&arm.body)),
+        Stmt::Try(stmt) => {
+            stmt.body.iter().any(contains_yield)
+                || stmt.catch_body.iter().flatten().any(contains_yield)
+                || stmt.finally_body.iter().flatten().any(contains_yield)
+        }
+        _ => false,
+    }
+}
+
+/// Where a suspended generator is inside one of the statements that contain a `yield`.
+/// The interpreter itself keeps this state on the Rust stack, which can't be suspended, so
+/// generators run their body one statement at a time instead. Statements without a `yield` in
+/// them are still run by the interpreter in one go.
+enum Frame {
+    /// The next statement to run in a list of them, and the scope they run in
+    Block {
+        index: usize,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// The branch that an if statement took
+    If { then: bool },
+    /// The arm that a match statement took, and the scope of its bindings
+    Arm {
+        index: usize,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// A while loop. Suspending with no frame for its body means that the body has finished.
+    Loop,
+    /// A for-in loop, with the scope of the current iteration
+    ForIn {
+        iterator: LoxIterator,
+        environment: Rc<RefCell<Environment>>,
+    },
+}
+
+enum GeneratorState {
+    Suspended(Vec<Frame>),
+    Running,
+    Done,
+}
+
+/// What calling a function with `yield` in its body returns. Its body only runs when `next()` is
+/// called, up to the following `yield`.
+pub struct LoxGenerator {
+    name: String,
+    body: Rc<Vec<Stmt>>,
+    /// The global scope of the module the function was declared in
+    globals: Rc<RefCell<Environment>>,
+    state: RefCell<GeneratorState>,
+}
+
+impl LoxGenerator {
+    pub fn new(
+        name: &str,
+        body: Rc<Vec<Stmt>>,
+        environment: Rc<RefCell<Environment>>,
+        globals: Rc<RefCell<Environment>>,
+    ) -> Self {
+        let frame = Frame::Block {
+            index: 0,
+            environment,
+        };
+        Self {
+            name: name.to_string(),
+            body,
+            globals,
+            state: RefCell::new(GeneratorState::Suspended(vec![frame])),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(*self.state.borrow(), GeneratorState::Done)
+    }
+
+    /// Runs the body until the next `yield` and returns the yielded value. Returns nil once the
+    /// body has finished, whether by reaching its end, a `return` or an error.
+    pub fn resume(&self, interp: &mut Interpreter, token: &Token) -> Result<Lit, LoxResult> {
+        let mut frames = match self.state.replace(GeneratorState::Running) {
+            GeneratorState::Suspended(frames) => frames,
+            GeneratorState::Running => {
+                return Err(LoxResult::runtime_error(
+                    token.clone(),
+                    "Generator is already running.",
+                ))
+            }
+            GeneratorState::Done => {
+                self.state.replace(GeneratorState::Done);
+                return Ok(Lit::Nil);
+            }
+        };
+
+        let globals = std::mem::replace(&mut interp.globals, Rc::clone(&self.globals));
+        let result = self.run_block(interp, &mut frames, 0, &self.body);
+        interp.globals = globals;
+
+        match result {
+            Ok(Some(value)) => {
+                self.state.replace(GeneratorState::Suspended(frames));
+                Ok(value)
+            }
+            Ok(None) | Err(LoxResult::ReturnValue { .. }) => {
+                self.state.replace(GeneratorState::Done);
+                Ok(Lit::Nil)
+            }
+            Err(e) => {
+                self.state.replace(GeneratorState::Done);
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs `statements` from where `frames[level]` left off. Returns the yielded value if it
+    /// suspended, or `None` once the statements are done.
+    fn run_block(
+        &self,
+        interp: &mut Interpreter,
+        frames: &mut Vec<Frame>,
+        level: usize,
+        statements: &[Stmt],
+    ) -> Result<Option<Lit>, LoxResult> {
+        loop {
+            let (index, environment) = match &frames[level] {
+                Frame::Block { index, environment } => (*index, Rc::clone(environment)),
+                _ => return Err(frame_error()),
+            };
+            let Some(stmt) = statements.get(index) else {
+                frames.truncate(level);
+                return Ok(None);
+            };
+
+            let yielded = self.run_stmt(interp, frames, level + 1, stmt, &environment)?;
+            // Only move on if the statement didn't suspend somewhere inside of itself
+            if frames.len() == level + 1 {
+                if let Frame::Block { index, .. } = &mut frames[level] {
+                    *index += 1;
+                }
+            }
+            if yielded.is_some() {
+                return Ok(yielded);
+            }
+        }
+    }
+
+    /// Runs or resumes `stmt`, whose frame, if it needs one, is `frames[level]`
+    fn run_stmt(
+        &self,
+        interp: &mut Interpreter,
+        frames: &mut Vec<Frame>,
+        level: usize,
+        stmt: &Stmt,
+        environment: &Rc<RefCell<Environment>>,
+    ) -> Result<Option<Lit>, LoxResult> {
+        let resuming = frames.len() > level;
+        if !resuming && !contains_yield(stmt) {
+            interp.execute_in(stmt, Rc::clone(environment))?;
+            return Ok(None);
+        }
+
+        match stmt {
+            Stmt::Yield(stmt) => {
+                let value = match &stmt.value {
+                    Some(value) => interp.evaluate_in(value, Rc::clone(environment))?,
+                    None => Lit::Nil,
+                };
+                Ok(Some(value))
+            }
+            Stmt::Block(block) => {
+                if !resuming {
+                    let environment = Environment::new_with_enclosing(Rc::clone(environment));
+                    frames.push(Frame::Block {
+                        index: 0,
+                        environment: Rc::new(RefCell::new(environment)),
+                    });
+                }
+                self.run_block(interp, frames, level, &block.statements)
+            }
+            Stmt::If(stmt) => {
+                if !resuming {
+                    let condition = interp.evaluate_in(&stmt.condition, Rc::clone(environment))?;
+                    frames.push(Frame::If {
+                        then: condition.is_truthy(),
+                    });
+                }
+                let branch = match frames[level] {
+                    Frame::If { then: true } => Some(stmt.then_branch.as_ref()),
+                    Frame::If { then: false } => stmt.else_branch.as_deref(),
+                    _ => return Err(frame_error()),
+                };
+
+                let yielded = match branch {
+                    Some(branch) => {
+                        self.run_stmt(interp, frames, level + 1, branch, environment)?
+                    }
+                    None => None,
+                };
+                if frames.len() == level + 1 {
+                    frames.truncate(level);
+                }
+                Ok(yielded)
+            }
+            Stmt::Match(stmt) => {
+                if !resuming {
+                    let (index, environment) = interp.match_arm(stmt, environment)?;
+                    frames.push(Frame::Arm { index, environment });
+                }
+                let (arm, scope) = match &frames[level] {
+                    Frame::Arm { index, environment } => {
+                        (&stmt.arms[*index], Rc::clone(environment))
+                    }
+                    _ => return Err(frame_error()),
+                };
+
+                let yielded = self.run_stmt(interp, frames, level + 1, &arm.body, &scope)?;
+                if frames.len() == level + 1 {
+                    frames.truncate(level);
+                }
+                Ok(yielded)
+            }
+            Stmt::While(stmt) => {
+                if !resuming {
+                    frames.push(Frame::Loop);
+                }
+                let mut increment = resuming && frames.len() == level + 1;
+
+                loop {
+                    if frames.len() == level + 1 {
+                        if let (true, Some(expr)) = (increment, &stmt.increment) {
+                            interp.evaluate_in(expr, Rc::clone(environment))?;
+                        }
+                        if !interp
+                            .evaluate_in(&stmt.condition, Rc::clone(environment))?
+                            .is_truthy()
+                        {
+                            frames.truncate(level);
+                            return Ok(None);
+                        }
+                    }
+
+                    match self.run_stmt(interp, frames, level + 1, &stmt.body, environment) {
+                        Ok(Some(value)) => return Ok(Some(value)),
+                        Ok(None) | Err(LoxResult::Continue) => {}
+                        Err(LoxResult::Break) => {
+                            frames.truncate(level);
+                            return Ok(None);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                    frames.truncate(level + 1);
+                    increment = true;
+                }
+            }
+            Stmt::ForIn(stmt) => {
+                if !resuming {
+                    let iterable = interp.evaluate_in(&stmt.iterable, Rc::clone(environment))?;
+                    frames.push(Frame::ForIn {
+                        iterator: LoxIterator::new(interp, iterable, &stmt.name)?,
+                        environment: Rc::clone(environment),
+                    });
+                }
+
+                loop {
+                    if frames.len() == level + 1 {
+                        let Frame::ForIn {
+                            iterator,
+                            environment: iteration,
+                        } = &mut frames[level]
+                        else {
+                            return Err(frame_error());
+                        };
+                        let Some(value) = iterator.next(interp, &stmt.name)? else {
+                            frames.truncate(level);
+                            return Ok(None);
+                        };
+                        let mut scope = Environment::new_with_enclosing(Rc::clone(environment));
+                        scope.bind(&stmt.name.lexeme, value);
+                        *iteration = Rc::new(RefCell::new(scope));
+                    }
+
+                    let iteration = match &frames[level] {
+                        Frame::ForIn { environment, .. } => Rc::clone(environment),
+                        _ => return Err(frame_error()),
+                    };
+                    match self.run_stmt(interp, frames, level + 1, &stmt.body, &iteration) {
+                        Ok(Some(value)) => return Ok(Some(value)),
+                        Ok(None) | Err(LoxResult::Continue) => {}
+                        Err(LoxResult::Break) => {
+                            frames.truncate(level);
+                            return Ok(None);
+                        }
+                        Err(e) => return Err(e),
+                    }
+                    frames.truncate(level + 1);
+                }
+            }
+            // The resolver doesn't allow yielding anywhere else
+            _ => Err(frame_error()),
+        }
+    }
+}
+
+fn frame_error() -> LoxResult {
+    LoxResult::system_error("Generator frames don't match its body.")
+}
+
+impl PartialEq for LoxGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Generator {}>", self.name)
+    }
+}
+
+impl fmt::Display for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator {}>", self.name)
+    }
+}
+
+/// The `next()` method of a generator
+pub struct GeneratorNext {
+    pub generator: Rc<LoxGenerator>,
+}
+
+impl LoxCallable for GeneratorNext {
+    fn call(
+        &self,
+        interp: &mut Interpreter,
+        _arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        self.generator.resume(interp, paren)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&[])
+    }
+}
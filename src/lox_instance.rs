@@ -0,0 +1,73 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::error::LoxResult;
+use crate::lit::Lit;
+use crate::lox_class::LoxClass;
+use crate::token::Token;
+
+pub struct LoxInstance {
+    pub class: Rc<LoxClass>,
+    fields: RefCell<HashMap<String, Lit>>,
+    /// The runtime error that a caught `Error` instance was made from, reported in its place if
+    /// the instance is thrown again and never caught
+    pub origin: Option<LoxResult>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        Self {
+            class,
+            fields: RefCell::new(HashMap::new()),
+            origin: None,
+        }
+    }
+
+    pub fn from_error(class: Rc<LoxClass>, error: LoxResult) -> Self {
+        Self {
+            origin: Some(error),
+            ..Self::new(class)
+        }
+    }
+
+    /// Fields shadow methods, methods are bound to this instance when they are accessed
+    pub fn get(self: &Rc<Self>, name: &Token) -> Result<Lit, LoxResult> {
+        if let Some(value) = self.fields.borrow().get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        if let Some(method) = self.class.find_method(&name.lexeme) {
+            return Ok(Lit::Func(Rc::new(method.bind(Rc::clone(self)))));
+        }
+
+        Err(LoxResult::runtime_error(
+            name.clone(),
+            &format!("Undefined property '{}'.", name.lexeme),
+        ))
+    }
+
+    pub fn set(&self, name: &Token, value: Lit) {
+        self.fields.borrow_mut().insert(name.lexeme.clone(), value);
+    }
+}
+
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxInstance {
+    // Fields may refer back to this instance, so only print the class
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Instance {}>", self.class.name)
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
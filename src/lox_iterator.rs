@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lit::Lit;
+use crate::lox_generator::LoxGenerator;
+use crate::lox_map::MapKey;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// The state of a for-in loop over any of the values that can be iterated
+pub enum LoxIterator {
+    /// Indexed on every step, so the loop body may push to the list
+    List {
+        list: Rc<RefCell<Vec<Lit>>>,
+        index: usize,
+    },
+    /// Maps and strings are iterated over a snapshot of their keys or characters
+    Values(std::vec::IntoIter<Lit>),
+    Range {
+        next: f64,
+        end: f64,
+    },
+    /// An object whose `next()` method returns the next value, or nil once it is done
+    Protocol(Lit),
+    /// Generators can yield nil too, so they are resumed directly and checked for being done
+    Generator(Rc<LoxGenerator>),
+}
+
+impl LoxIterator {
+    /// Instances are iterated by calling their `iter()` method if they have one, otherwise the
+    /// instance is its own iterator
+    pub fn new(interp: &mut Interpreter, iterable: Lit, token: &Token) -> Result<Self, LoxResult> {
+        match iterable {
+            Lit::List(list) => Ok(LoxIterator::List { list, index: 0 }),
+            Lit::Map(map) => {
+                let mut keys = map.borrow().keys().cloned().collect::<Vec<MapKey>>();
+                keys.sort();
+                let keys = keys.iter().map(|k| k.to_lit()).collect::<Vec<Lit>>();
+                Ok(LoxIterator::Values(keys.into_iter()))
+            }
+            Lit::Str(s) => {
+                let chars = s.chars().map(|c| Lit::Str(c.to_string()));
+                Ok(LoxIterator::Values(chars.collect::<Vec<Lit>>().into_iter()))
+            }
+            Lit::Range(start, end) => Ok(LoxIterator::Range { next: start, end }),
+            Lit::Instance(instance) if instance.class.find_method("iter").is_some() => {
+                let iter = Token::new(TokenType::Identifier, "iter", None, token.line);
+                let iter = interp.get_property(Lit::Instance(instance), &iter)?;
+                Ok(LoxIterator::Protocol(interp.call_with_values(
+                    iter,
+                    Vec::new(),
+                    token,
+                )?))
+            }
+            Lit::Instance(_) => Ok(LoxIterator::Protocol(iterable)),
+            Lit::Generator(generator) => Ok(LoxIterator::Generator(generator)),
+            _ => Err(LoxResult::runtime_error(
+                token.clone(),
+                "Can only iterate over lists, maps, strings, ranges and iterators.",
+            )),
+        }
+    }
+
+    pub fn next(
+        &mut self,
+        interp: &mut Interpreter,
+        token: &Token,
+    ) -> Result<Option<Lit>, LoxResult> {
+        match self {
+            LoxIterator::List { list, index } => {
+                let element = list.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(element)
+            }
+            LoxIterator::Values(values) => Ok(values.next()),
+            LoxIterator::Range { next, end } => {
+                if next < end {
+                    let n = *next;
+                    *next += 1.0;
+                    Ok(Some(Lit::Num(n)))
+                } else {
+                    Ok(None)
+                }
+            }
+            LoxIterator::Protocol(iterator) => {
+                let next = Token::new(TokenType::Identifier, "next", None, token.line);
+                let next = interp.get_property(iterator.clone(), &next)?;
+                match interp.call_with_values(next, Vec::new(), token)? {
+                    Lit::Nil => Ok(None),
+                    value => Ok(Some(value)),
+                }
+            }
+            LoxIterator::Generator(generator) => {
+                let value = generator.resume(interp, token)?;
+                Ok((!generator.is_done()).then_some(value))
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::cmp::Ordering;
+
+use crate::error::LoxResult;
+use crate::lit::Lit;
+use crate::token::Token;
+
+/// The hashable subset of [`Lit`] that can be used as a map key.
+///
+/// Numbers are stored as their bit pattern. `-0` is normalised to `0` so that keys agree with
+/// `==`, and `NaN` is rejected outright since it isn't equal to anything, itself included.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Nil,
+    Bool(bool),
+    Num(u64),
+    Str(String),
+}
+
+impl MapKey {
+    pub fn from_lit(lit: &Lit, token: &Token) -> Result<Self, LoxResult> {
+        match lit {
+            Lit::Nil => Ok(MapKey::Nil),
+            Lit::Bool(b) => Ok(MapKey::Bool(*b)),
+            Lit::Num(n) if n.is_nan() => Err(LoxResult::runtime_error(
+                token.clone(),
+                "NaN can't be used as a map key.",
+            )),
+            // Adding 0.0 turns -0.0 into 0.0 and leaves everything else untouched
+            Lit::Num(n) => Ok(MapKey::Num((n + 0.0).to_bits())),
+            Lit::Str(s) => Ok(MapKey::Str(s.clone())),
+            _ => Err(LoxResult::runtime_error(
+                token.clone(),
+                "Map keys must be strings, numbers, booleans or nil.",
+            )),
+        }
+    }
+
+    pub fn to_lit(&self) -> Lit {
+        match self {
+            MapKey::Nil => Lit::Nil,
+            MapKey::Bool(b) => Lit::Bool(*b),
+            MapKey::Num(bits) => Lit::Num(f64::from_bits(*bits)),
+            MapKey::Str(s) => Lit::Str(s.clone()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            MapKey::Nil => 0,
+            MapKey::Bool(_) => 1,
+            MapKey::Num(_) => 2,
+            MapKey::Str(_) => 3,
+        }
+    }
+}
+
+/// Keys are ordered by type first (nil, booleans, numbers, strings) and then by value, which
+/// gives maps a stable order when printed or iterated.
+impl Ord for MapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (MapKey::Bool(a), MapKey::Bool(b)) => a.cmp(b),
+            (MapKey::Num(a), MapKey::Num(b)) => f64::from_bits(*a).total_cmp(&f64::from_bits(*b)),
+            (MapKey::Str(a), MapKey::Str(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for MapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,52 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::lit::Lit;
+use crate::token::Token;
+
+/// A file that was run by an `import`, along with the global scope it defined its bindings in
+pub struct LoxModule {
+    pub name: String,
+    globals: Rc<RefCell<Environment>>,
+}
+
+impl LoxModule {
+    pub fn new(name: &str, globals: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            name: name.to_string(),
+            globals,
+        }
+    }
+
+    /// Only the module's own top level bindings are exposed, not the builtins it can see
+    pub fn get(&self, name: &Token) -> Result<Lit, LoxResult> {
+        self.globals.borrow().get_own(&name.lexeme).ok_or_else(|| {
+            LoxResult::runtime_error(
+                name.clone(),
+                &format!("Module '{}' has no binding '{}'.", self.name, name.lexeme),
+            )
+        })
+    }
+}
+
+impl PartialEq for LoxModule {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxModule {
+    // The module's bindings may refer back to it, so only print the name
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Module {}>", self.name)
+    }
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
@@ -1,4 +1,6 @@
 use core::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::time::SystemTime;
 
@@ -6,6 +8,9 @@ use crate::error::LoxResult;
 use crate::interpreter::Interpreter;
 use crate::lit::Lit;
 use crate::lox_callable::LoxCallable;
+use crate::lox_map::MapKey;
+use crate::signature::Signature;
+use crate::token::Token;
 
 #[derive(Clone)]
 pub struct LoxNative {
@@ -14,7 +19,7 @@ pub struct LoxNative {
 
 impl PartialEq for LoxNative {
     fn eq(&self, other: &Self) -> bool {
-        Rc::as_ptr(&self.func) == Rc::as_ptr(&other.func)
+        std::ptr::addr_eq(Rc::as_ptr(&self.func), Rc::as_ptr(&other.func))
     }
 }
 
@@ -35,8 +40,9 @@ pub struct NativeClock;
 impl LoxCallable for NativeClock {
     fn call(
         &self,
-        _interp: &Interpreter,
-        arguments: Vec<crate::lit::Lit>,
+        _interp: &mut Interpreter,
+        _arguments: Vec<Lit>,
+        _paren: &Token,
     ) -> Result<Lit, LoxResult> {
         match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
             Ok(n) => Ok(Lit::Num(n.as_millis() as f64)),
@@ -47,7 +53,205 @@ impl LoxCallable for NativeClock {
         }
     }
 
-    fn arity(&self) -> usize {
-        0
+    fn signature(&self) -> Signature {
+        Signature::new(&[])
+    }
+}
+
+fn expect_list(arg: &Lit, paren: &Token, name: &str) -> Result<Rc<RefCell<Vec<Lit>>>, LoxResult> {
+    match arg {
+        Lit::List(list) => Ok(Rc::clone(list)),
+        _ => Err(LoxResult::runtime_error(
+            paren.clone(),
+            &format!("Expected a list as the first argument to '{}'.", name),
+        )),
+    }
+}
+
+fn expect_map(
+    arg: &Lit,
+    paren: &Token,
+    name: &str,
+) -> Result<Rc<RefCell<HashMap<MapKey, Lit>>>, LoxResult> {
+    match arg {
+        Lit::Map(map) => Ok(Rc::clone(map)),
+        _ => Err(LoxResult::runtime_error(
+            paren.clone(),
+            &format!("Expected a map as the first argument to '{}'.", name),
+        )),
+    }
+}
+
+pub struct NativeLen;
+
+impl LoxCallable for NativeLen {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        match &arguments[0] {
+            Lit::List(list) => Ok(Lit::Num(list.borrow().len() as f64)),
+            Lit::Map(map) => Ok(Lit::Num(map.borrow().len() as f64)),
+            Lit::Str(s) => Ok(Lit::Num(s.chars().count() as f64)),
+            _ => Err(LoxResult::runtime_error(
+                paren.clone(),
+                "Can only take the length of lists, maps and strings.",
+            )),
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["value"])
+    }
+}
+
+/// Appends the value to the end of the list
+pub struct NativePush;
+
+impl LoxCallable for NativePush {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        mut arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let list = expect_list(&arguments[0], paren, "push")?;
+        list.borrow_mut().push(arguments.remove(1));
+        Ok(Lit::Nil)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["list", "value"])
+    }
+}
+
+/// Removes and returns the last value of the list
+pub struct NativePop;
+
+impl LoxCallable for NativePop {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let list = expect_list(&arguments[0], paren, "pop")?;
+        let value = list.borrow_mut().pop();
+        value
+            .ok_or_else(|| LoxResult::runtime_error(paren.clone(), "Can't pop from an empty list."))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["list"])
+    }
+}
+
+/// Inserts the value before the given index, an index equal to the length appends to the list
+pub struct NativeInsert;
+
+impl LoxCallable for NativeInsert {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        mut arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let list = expect_list(&arguments[0], paren, "insert")?;
+        let len = list.borrow().len();
+        let index = arguments[1].to_index(paren, len + 1)?;
+        list.borrow_mut().insert(index, arguments.remove(2));
+        Ok(Lit::Nil)
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["list", "index", "value"])
+    }
+}
+
+/// Returns a new list of the map's keys, in the same order the map is printed in
+pub struct NativeKeys;
+
+impl LoxCallable for NativeKeys {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let map = expect_map(&arguments[0], paren, "keys")?;
+        let mut keys = map.borrow().keys().cloned().collect::<Vec<MapKey>>();
+        keys.sort();
+        let keys = keys.iter().map(|k| k.to_lit()).collect();
+        Ok(Lit::List(Rc::new(RefCell::new(keys))))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["map"])
+    }
+}
+
+/// Returns a new list of the map's values, ordered by their keys
+pub struct NativeValues;
+
+impl LoxCallable for NativeValues {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let map = expect_map(&arguments[0], paren, "values")?;
+        let map = map.borrow();
+        let mut entries = map.iter().collect::<Vec<(&MapKey, &Lit)>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let values = entries.into_iter().map(|(_, v)| v.clone()).collect();
+        Ok(Lit::List(Rc::new(RefCell::new(values))))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["map"])
+    }
+}
+
+pub struct NativeHas;
+
+impl LoxCallable for NativeHas {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let map = expect_map(&arguments[0], paren, "has")?;
+        let key = MapKey::from_lit(&arguments[1], paren)?;
+        let has = map.borrow().contains_key(&key);
+        Ok(Lit::Bool(has))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["map", "key"])
+    }
+}
+
+/// Removes the key from the map and returns its value, or nil if it wasn't there
+pub struct NativeRemove;
+
+impl LoxCallable for NativeRemove {
+    fn call(
+        &self,
+        _interp: &mut Interpreter,
+        arguments: Vec<Lit>,
+        paren: &Token,
+    ) -> Result<Lit, LoxResult> {
+        let map = expect_map(&arguments[0], paren, "remove")?;
+        let key = MapKey::from_lit(&arguments[1], paren)?;
+        let removed = map.borrow_mut().remove(&key);
+        Ok(removed.unwrap_or(Lit::Nil))
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::new(&["map", "key"])
     }
 }
@@ -0,0 +1,30 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::lox_function::LoxFunction;
+
+/// A named set of methods that classes can mix in with `class C with T`. The methods are copied
+/// into the class, which binds `this` to its instances like it does for its own methods.
+pub struct LoxTrait {
+    pub name: String,
+    pub methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl PartialEq for LoxTrait {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Debug for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Trait {}>", self.name)
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
@@ -5,10 +5,21 @@ pub mod expr;
 pub mod interpreter;
 pub mod lit;
 pub mod lox_callable;
+pub mod lox_class;
+pub mod lox_enum;
 pub mod lox_function;
+pub mod lox_generator;
+pub mod lox_instance;
+pub mod lox_iterator;
+pub mod lox_map;
+pub mod lox_module;
 pub mod lox_native;
+pub mod lox_trait;
 pub mod parser;
+pub mod pattern;
+pub mod resolver;
 pub mod scanner;
+pub mod signature;
 pub mod stmt;
 pub mod token;
 pub mod token_type;
@@ -17,12 +28,14 @@ use std::{
     env::args,
     fs::File,
     io::{self, BufRead, Read, Write},
+    path::Path,
 };
 
 //use ast_printer::AstPrinter;
 use error::*;
 use interpreter::*;
 use parser::Parser;
+use resolver::Resolver;
 use scanner::*;
 
 fn main() {
@@ -54,6 +67,7 @@ impl Lox {
     pub fn run_file(&mut self, path: &str) -> io::Result<()> {
         let mut inp = String::new();
         File::open(path)?.read_to_string(&mut inp).unwrap();
+        self.interpreter.set_script(Path::new(path));
         if self.run(inp).is_err() {
             // Ignore - error was already reported
             std::process::exit(65);
@@ -92,7 +106,14 @@ impl Lox {
 
         let statements = parser.parse()?;
 
-        if parser.success() {
+        if !parser.success() {
+            return Ok(());
+        }
+
+        let mut resolver = Resolver::new();
+        resolver.resolve(&statements)?;
+
+        if resolver.success() {
             self.interpreter.interpret(&statements);
         }
         Ok(())
@@ -1,4 +1,9 @@
-use crate::{error::*, expr::*, lit::*, stmt::*, token::*, token_type::*};
+use std::{cell::Cell, rc::Rc};
+
+use crate::{
+    error::*, expr::*, lit::*, lox_enum::Variant, lox_map::MapKey, pattern::*, signature::*,
+    stmt::*, token::*, token_type::*,
+};
 
 pub struct Parser {
     tokens: Vec<Token>,
@@ -28,7 +33,8 @@ impl Parser {
         if self.matches(&[TokenType::Print]) {
             return self.print_statement();
         }
-        if self.matches(&[TokenType::LeftBrace]) {
+        if self.check(TokenType::LeftBrace) && !self.is_destructuring() {
+            self.advance();
             return Ok(Stmt::Block(BlockStmt {
                 statements: self.block()?,
             }));
@@ -42,11 +48,53 @@ impl Parser {
         if self.matches(&[TokenType::For]) {
             return self.for_statement();
         }
+        if self.matches(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.matches(&[TokenType::Import]) {
+            return self.import_statement();
+        }
+        if self.matches(&[TokenType::Match]) {
+            return self.match_statement();
+        }
+        if self.matches(&[TokenType::Yield]) {
+            let keyword = self.previous();
+            let value = if self.check(TokenType::Semicolon) {
+                None
+            } else {
+                Some(self.expression()?)
+            };
+            self.consume(TokenType::Semicolon, "Expect ';' after yielded value.")?;
+            return Ok(Stmt::Yield(YieldStmt { keyword, value }));
+        }
+        if self.matches(&[TokenType::Throw]) {
+            let keyword = self.previous();
+            let value = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expect ';' after thrown value.")?;
+            return Ok(Stmt::Throw(ThrowStmt { keyword, value }));
+        }
+        if self.matches(&[TokenType::Try]) {
+            return self.try_statement();
+        }
+        if self.matches(&[TokenType::Break]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Semicolon, "Expect ';' after 'break'.")?;
+            return Ok(Stmt::Break(BreakStmt { keyword }));
+        }
+        if self.matches(&[TokenType::Continue]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Semicolon, "Expect ';' after 'continue'.")?;
+            return Ok(Stmt::Continue(ContinueStmt { keyword }));
+        }
         self.expression_statement()
     }
 
     fn for_statement(&mut self) -> Result<Stmt, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        if self.check(TokenType::Identifier) && self.check_next(TokenType::In) {
+            return self.for_in_statement();
+        }
+
         let initiliazer = if self.matches(&[TokenType::Semicolon]) {
             None
         } else if self.matches(&[TokenType::Var]) {
@@ -69,24 +117,15 @@ impl Parser {
         };
         self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
 
-        let mut body = self.statement()?;
-
-        if let Some(increment) = increment {
-            body = Stmt::Block(BlockStmt {
-                statements: vec![
-                    body,
-                    Stmt::Expression(ExpressionStmt {
-                        expression: increment,
-                    }),
-                ],
-            });
-        }
+        let body = self.statement()?;
 
-        body = Stmt::While(WhileStmt {
+        // The increment is kept separate from the body so that `continue` still runs it
+        let mut body = Stmt::While(WhileStmt {
             condition: condition.unwrap_or(Expr::Literal(LiteralExpr {
                 value: Some(Lit::Bool(true)),
             })),
             body: Box::new(body),
+            increment,
         });
 
         if let Some(initializer) = initiliazer {
@@ -98,6 +137,312 @@ impl Parser {
         Ok(body)
     }
 
+    fn for_in_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.advance();
+        self.advance();
+        let iterable = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+        let body = self.statement()?;
+
+        Ok(Stmt::ForIn(ForInStmt {
+            name,
+            iterable,
+            body: Box::new(body),
+        }))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous();
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+
+        Ok(Stmt::Return(ReturnStmt { keyword, value }))
+    }
+
+    /// Parses `import "path" as name;`, `import { a, b } from "path";` or just `import "path";`.
+    /// `as` and `from` are only keywords in here, so they are matched as identifiers.
+    fn import_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous();
+
+        let mut names = Vec::new();
+        if self.matches(&[TokenType::LeftBrace]) {
+            loop {
+                names.push(self.consume(TokenType::Identifier, "Expect imported name.")?);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after imported names.")?;
+            self.consume_word("from", "Expect 'from' after imported names.")?;
+        }
+
+        let path = self.consume(TokenType::String, "Expect module path.")?;
+        let alias = if names.is_empty() && self.check_word("as") {
+            self.advance();
+            Some(self.consume(TokenType::Identifier, "Expect module name after 'as'.")?)
+        } else {
+            None
+        };
+
+        self.consume(TokenType::Semicolon, "Expect ';' after import.")?;
+        Ok(Stmt::Import(ImportStmt {
+            keyword,
+            path,
+            alias,
+            names,
+        }))
+    }
+
+    fn match_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let subject = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after match value.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match arms.")?;
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            let guard = if self.matches(&[TokenType::If]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(TokenType::EqualGreater, "Expect '=>' after match pattern.")?;
+            let body = self.statement()?;
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            // Arms may be separated by commas, but their statements already end them
+            self.matches(&[TokenType::Comma]);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after match arms.")?;
+        Ok(Stmt::Match(MatchStmt {
+            keyword,
+            subject,
+            arms,
+        }))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, LoxResult> {
+        let pattern = self.single_pattern()?;
+        if !self.check(TokenType::Pipe) {
+            return Ok(pattern);
+        }
+
+        let mut alternatives = vec![pattern];
+        while self.matches(&[TokenType::Pipe]) {
+            alternatives.push(self.single_pattern()?);
+        }
+        Ok(Pattern::Alternatives(alternatives))
+    }
+
+    fn single_pattern(&mut self) -> Result<Pattern, LoxResult> {
+        // `_` is an ordinary name everywhere but in patterns
+        if self.check_word("_") {
+            self.advance();
+            return Ok(Pattern::Wildcard);
+        }
+        if self.matches(&[TokenType::Identifier]) {
+            let name = self.previous();
+            if !self.matches(&[TokenType::Dot]) {
+                return Ok(Pattern::Binding(name));
+            }
+
+            let variant = self.consume(TokenType::Identifier, "Expect variant name after '.'.")?;
+            let mut fields = Vec::new();
+            if self.matches(&[TokenType::LeftParen]) {
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        fields.push(self.pattern()?);
+                        if !self.matches(&[TokenType::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after variant patterns.")?;
+            }
+            return Ok(Pattern::Variant {
+                enum_name: name,
+                name: variant,
+                fields,
+                depth: Cell::new(None),
+            });
+        }
+
+        if self.matches(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    if self.matches(&[TokenType::DotDotDot]) {
+                        rest = Some(self.consume(
+                            TokenType::Identifier,
+                            "Expect a name for the rest after '...'.",
+                        )?);
+                        break;
+                    }
+                    elements.push(self.pattern()?);
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list pattern.")?;
+            return Ok(Pattern::List { elements, rest });
+        }
+
+        if self.matches(&[TokenType::LeftBrace]) {
+            let mut entries = Vec::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    if self.check(TokenType::Identifier) {
+                        let name = self.advance();
+                        let key = MapKey::Str(name.lexeme.clone());
+                        entries.push((key, Pattern::Binding(name)));
+                    } else {
+                        let (key, token) = self.pattern_literal()?;
+                        let key = MapKey::from_lit(&key, &token)
+                            .map_err(|_| self.error(token, "Invalid map key."))?;
+                        self.consume(TokenType::Colon, "Expect ':' after map pattern key.")?;
+                        entries.push((key, self.pattern()?));
+                    }
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after map pattern.")?;
+            return Ok(Pattern::Map(entries));
+        }
+
+        let (literal, token) = self.pattern_literal()?;
+        if self.matches(&[TokenType::DotDot]) {
+            let (end, _) = self.pattern_literal()?;
+            return match (literal, end) {
+                (Lit::Num(start), Lit::Num(end)) => Ok(Pattern::Range(start, end)),
+                _ => Err(self.error(token, "Range patterns need numbers.")),
+            };
+        }
+        Ok(Pattern::Literal(literal))
+    }
+
+    /// Patterns can't contain arbitrary expressions, only literals and negative numbers
+    fn pattern_literal(&mut self) -> Result<(Lit, Token), LoxResult> {
+        let negative = self.matches(&[TokenType::Minus]);
+        let token = self.peek();
+        let literal = match token.ttype {
+            TokenType::Number | TokenType::String => token.literal.clone(),
+            TokenType::True => Some(Lit::Bool(true)),
+            TokenType::False => Some(Lit::Bool(false)),
+            TokenType::Nil => Some(Lit::Nil),
+            _ => None,
+        };
+
+        match (literal, negative) {
+            (Some(Lit::Num(n)), true) => {
+                self.advance();
+                Ok((Lit::Num(-n), token))
+            }
+            (Some(literal), false) => {
+                self.advance();
+                Ok((literal, token))
+            }
+            _ => Err(self.error(token, "Expect pattern.")),
+        }
+    }
+
+    /// Parses a pattern that declarations, assignments and parameters take their value apart with
+    fn destructuring_pattern(&mut self) -> Result<Pattern, LoxResult> {
+        let start = self.peek();
+        let pattern = self.pattern()?;
+        if !pattern.is_destructuring() {
+            return Err(self.error(
+                start,
+                "Can only destructure into names, '_', lists and maps.",
+            ));
+        }
+        Ok(pattern)
+    }
+
+    /// Whether the '[' or '{' at the current token starts a pattern that gets assigned to, rather
+    /// than a list, a map or a block. Only looks ahead over the tokens that a pattern can contain,
+    /// so this never backtracks over a parsed expression.
+    fn is_destructuring(&self) -> bool {
+        let mut depth = 0;
+        let mut i = self.current;
+        loop {
+            match self.tokens[i].ttype {
+                TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                TokenType::RightBracket | TokenType::RightBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                TokenType::Identifier
+                | TokenType::Comma
+                | TokenType::Colon
+                | TokenType::DotDotDot
+                | TokenType::String
+                | TokenType::Number
+                | TokenType::Minus
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Nil => {}
+                _ => return false,
+            }
+            i += 1;
+        }
+        self.tokens
+            .get(i + 1)
+            .is_some_and(|t| t.is(TokenType::Equal))
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous();
+        self.consume(TokenType::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let mut catch_name = None;
+        let mut catch_body = None;
+        if self.matches(&[TokenType::Catch]) {
+            // The caught value doesn't have to be bound to a name
+            if self.matches(&[TokenType::LeftParen]) {
+                catch_name =
+                    Some(self.consume(TokenType::Identifier, "Expect catch variable name.")?);
+                self.consume(TokenType::RightParen, "Expect ')' after catch variable.")?;
+            }
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'catch'.")?;
+            catch_body = Some(self.block()?);
+        }
+
+        let finally_body = if self.matches(&[TokenType::Finally]) {
+            self.consume(TokenType::LeftBrace, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+
+        if catch_body.is_none() && finally_body.is_none() {
+            return Err(self.error(keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Stmt::Try(TryStmt {
+            body,
+            catch_name,
+            catch_body,
+            finally_body,
+        }))
+    }
+
     fn while_statement(&mut self) -> Result<Stmt, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
@@ -107,6 +452,7 @@ impl Parser {
         Ok(Stmt::While(WhileStmt {
             condition,
             body: Box::new(body),
+            increment: None,
         }))
     }
 
@@ -139,42 +485,236 @@ impl Parser {
         self.consume(TokenType::Semicolon, "Expected ';' after value;")?;
         Ok(Stmt::Expression(ExpressionStmt { expression: value }))
     }
-    fn function(&mut self, kind: &str) -> Result<Stmt, LoxResult> {
+    fn class_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.")?;
+
+        let superclass = if self.matches(&[TokenType::Less]) {
+            Some(VariableExpr {
+                name: self.consume(TokenType::Identifier, "Expect superclass name.")?,
+                depth: Cell::new(None),
+            })
+        } else {
+            None
+        };
+
+        let mut traits = Vec::new();
+        if self.check_word("with") {
+            self.advance();
+            loop {
+                traits.push(VariableExpr {
+                    name: self.consume(TokenType::Identifier, "Expect trait name.")?,
+                    depth: Cell::new(None),
+                });
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        let mut getters = Vec::new();
+        let mut setters = Vec::new();
+        let mut statics = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            // `static` and `set` are only keywords in front of a method name
+            if self.check_word("static") && self.check_next(TokenType::Identifier) {
+                self.advance();
+                statics.push(self.function("static method")?);
+            } else if self.check_word("set") && self.check_next(TokenType::Identifier) {
+                self.advance();
+                let setter = self.function("setter")?;
+                if setter.params.len() != 1 || setter.rest.is_some() {
+                    self.error(setter.name.clone(), "A setter takes exactly one parameter.");
+                }
+                setters.push(setter);
+            } else if self.check(TokenType::Identifier) && self.check_next(TokenType::LeftBrace) {
+                let name = self.advance();
+                self.advance();
+                getters.push(FunctionStmt {
+                    name,
+                    params: Rc::new(Vec::new()),
+                    rest: None,
+                    body: Rc::new(self.block()?),
+                });
+            } else {
+                methods.push(self.function("method")?);
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        // Getters are looked up before methods, which would leave the method unreachable
+        for getter in &getters {
+            if methods
+                .iter()
+                .any(|method| method.name.lexeme == getter.name.lexeme)
+            {
+                self.error(
+                    getter.name.clone(),
+                    "A class can't have a getter and a method with the same name.",
+                );
+            }
+        }
+        Ok(Stmt::Class(ClassStmt {
+            name,
+            superclass,
+            traits,
+            methods,
+            getters,
+            setters,
+            statics,
+        }))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function("method")?);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after trait body.")?;
+        Ok(Stmt::Trait(TraitStmt { name, methods }))
+    }
+
+    fn function(&mut self, kind: &str) -> Result<FunctionStmt, LoxResult> {
         let name = self.consume(TokenType::Identifier, &format!("Expect {} name", kind))?;
+        self.function_body(name, kind)
+    }
+
+    /// Parses the parameter list and block body of a function, starting at its '('
+    fn function_body(&mut self, name: Token, kind: &str) -> Result<FunctionStmt, LoxResult> {
         self.consume(
             TokenType::LeftParen,
             &format!("Expect '(' after {} name.", kind),
         )?;
+        let (params, rest) = self.parameters()?;
 
-        let mut params = Vec::new();
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+
+        let body = self.block()?;
+        Ok(FunctionStmt {
+            name,
+            params: Rc::new(params),
+            rest,
+            body: Rc::new(body),
+        })
+    }
+
+    /// Parses a parameter list up to and including the closing ')'. Returns the parameters along
+    /// with the `...rest` one, which has to come last.
+    fn parameters(&mut self) -> Result<(Vec<Parameter>, Option<Token>), LoxResult> {
+        let mut params: Vec<Parameter> = Vec::new();
+        let mut rest = None;
         if !self.check(TokenType::RightParen) {
-            params.push(self.consume(TokenType::Identifier, "Expect paramter name.")?);
-            while self.matches(&[TokenType::Comma]) {
-                if params.len() >= 255 {
-                    if !self.had_error {
-                        let peek = self.peek();
-                        self.error(peek, "Can't have more than 255 parameters.");
+            loop {
+                if params.len() >= 255 && !self.had_error {
+                    let peek = self.peek();
+                    self.error(peek, "Can't have more than 255 parameters.");
+                }
+                if self.matches(&[TokenType::DotDotDot]) {
+                    rest =
+                        Some(self.consume(TokenType::Identifier, "Expect rest parameter name.")?);
+                    break;
+                }
+
+                let (name, pattern) =
+                    if self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace) {
+                        let open = self.peek();
+                        let pattern = self.destructuring_pattern()?;
+                        (Self::pattern_name(open, &pattern), Some(pattern))
+                    } else {
+                        let name = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                        (name, None)
+                    };
+                let default = if self.matches(&[TokenType::Equal]) {
+                    Some(self.expression()?)
+                } else {
+                    if params.iter().any(|param| param.default.is_some()) {
+                        self.error(
+                            name.clone(),
+                            "Parameters after one with a default value need one too.",
+                        );
                     }
+                    None
+                };
+                params.push(Parameter {
+                    name,
+                    pattern,
+                    default,
+                });
+
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
                 }
-                params.push(self.consume(TokenType::Identifier, "Expect paramater name.")?);
             }
         }
 
         self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        Ok((params, rest))
+    }
 
-        self.consume(
-            TokenType::LeftBrace,
-            &format!("Expect '{{' before {} body.", kind),
-        )?;
+    /// Makes up a name for a destructured parameter out of the names it binds, like `[a, b]`
+    fn pattern_name(open: Token, pattern: &Pattern) -> Token {
+        let names: Vec<&str> = pattern
+            .bindings()
+            .iter()
+            .map(|name| name.lexeme.as_str())
+            .collect();
+        let lexeme = match pattern {
+            Pattern::Map(_) => format!("{{{}}}", names.join(", ")),
+            _ => format!("[{}]", names.join(", ")),
+        };
+        Token::new(open.ttype, &lexeme, None, open.line)
+    }
 
-        let body = self.block()?;
-        Ok(Stmt::Function(FunctionStmt {
-            name,
-            params,
-            body,
+    /// Parses an arrow function whose '(' is the current token. Its body is a single expression
+    /// that gets returned.
+    fn arrow_function(&mut self) -> Result<Expr, LoxResult> {
+        let paren = self.advance();
+        let (params, rest) = self.parameters()?;
+        let arrow = self.consume(TokenType::EqualGreater, "Expect '=>' after parameters.")?;
+        let value = self.expression()?;
+
+        Ok(Expr::Lambda(LambdaExpr {
+            declaration: FunctionStmt {
+                name: Token::new(TokenType::Fun, "lambda", None, paren.line),
+                params: Rc::new(params),
+                rest,
+                body: Rc::new(vec![Stmt::Return(ReturnStmt {
+                    keyword: arrow,
+                    value: Some(value),
+                })]),
+            },
         }))
     }
 
+    /// Whether the '(' at the current token starts the parameter list of an arrow function rather
+    /// than a grouping. Only looks ahead over identifiers, commas and `...`, so this never
+    /// backtracks over a parsed expression. That leaves out default values and destructured
+    /// parameters, which need `fun`.
+    fn is_arrow_function(&self) -> bool {
+        let mut i = self.current + 1;
+        while matches!(
+            self.tokens[i].ttype,
+            TokenType::Identifier | TokenType::Comma | TokenType::DotDotDot
+        ) {
+            i += 1;
+        }
+        self.tokens[i].is(TokenType::RightParen)
+            && self
+                .tokens
+                .get(i + 1)
+                .is_some_and(|t| t.is(TokenType::EqualGreater))
+    }
+
     fn block(&mut self) -> Result<Vec<Stmt>, LoxResult> {
         let mut statements = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
@@ -189,10 +729,19 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Result<Stmt, LoxResult> {
-        let result = if self.matches(&[TokenType::Fun]) {
-            self.function("fucntion")
+        let result = if self.matches(&[TokenType::Class]) {
+            self.class_declaration()
+        } else if self.check(TokenType::Fun) && self.check_next(TokenType::Identifier) {
+            self.advance();
+            self.function("function").map(Stmt::Function)
         } else if self.matches(&[TokenType::Var]) {
             self.var_declaration()
+        } else if self.matches(&[TokenType::Const]) {
+            self.const_declaration()
+        } else if self.matches(&[TokenType::Trait]) {
+            self.trait_declaration()
+        } else if self.matches(&[TokenType::Enum]) {
+            self.enum_declaration()
         } else {
             self.statement()
         };
@@ -205,6 +754,24 @@ impl Parser {
     }
 
     fn var_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        if self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace) {
+            let pattern = self.destructuring_pattern()?;
+            let equals = self.consume(
+                TokenType::Equal,
+                "Expect '=' after pattern, destructuring needs a value.",
+            )?;
+            let initializer = self.expression()?;
+            self.consume(
+                TokenType::Semicolon,
+                "Expected ';' after variable declaration.",
+            )?;
+            return Ok(Stmt::Destructure(DestructureStmt {
+                pattern,
+                equals,
+                initializer,
+            }));
+        }
+
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
 
         let initializer = if self.matches(&[TokenType::Equal]) {
@@ -220,22 +787,183 @@ impl Parser {
         Ok(Stmt::Var(VarStmt { name, initializer }))
     }
 
+    fn const_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect constant name.")?;
+        self.consume(
+            TokenType::Equal,
+            "Expect '=' after constant name, constants must be initialized.",
+        )?;
+        let initializer = self.expression()?;
+        self.consume(
+            TokenType::Semicolon,
+            "Expected ';' after constant declaration.",
+        )?;
+        Ok(Stmt::Const(ConstStmt { name, initializer }))
+    }
+
+    fn enum_declaration(&mut self) -> Result<Stmt, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+
+        let mut variants: Vec<Variant> = Vec::new();
+        if !self.check(TokenType::RightBrace) {
+            loop {
+                let name = self.consume(TokenType::Identifier, "Expect variant name.")?;
+                if variants
+                    .iter()
+                    .any(|variant| variant.name.lexeme == name.lexeme)
+                {
+                    self.error(
+                        name.clone(),
+                        "Already a variant with this name in this enum.",
+                    );
+                }
+
+                let mut fields: Vec<Token> = Vec::new();
+                if self.matches(&[TokenType::LeftParen]) {
+                    if !self.check(TokenType::RightParen) {
+                        loop {
+                            let field =
+                                self.consume(TokenType::Identifier, "Expect field name.")?;
+                            if fields.iter().any(|f| f.lexeme == field.lexeme) {
+                                self.error(field.clone(), "Already a field with this name.");
+                            }
+                            fields.push(field);
+                            if !self.matches(&[TokenType::Comma]) {
+                                break;
+                            }
+                        }
+                    }
+                    self.consume(TokenType::RightParen, "Expect ')' after variant fields.")?;
+                }
+                variants.push(Variant { name, fields });
+
+                // A trailing comma is allowed
+                if !self.matches(&[TokenType::Comma]) || self.check(TokenType::RightBrace) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after enum body.")?;
+        Ok(Stmt::Enum(EnumStmt { name, variants }))
+    }
+
     fn assignment(&mut self) -> Result<Expr, LoxResult> {
-        let expr = self.or()?;
+        if (self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace))
+            && self.is_destructuring()
+        {
+            let pattern = self.destructuring_pattern()?;
+            let equals = self.consume(TokenType::Equal, "Expect '=' after pattern.")?;
+            let value = self.expression()?;
+            let depths = pattern.bindings().iter().map(|_| Cell::new(None)).collect();
+            return Ok(Expr::Destructure(DestructureExpr {
+                pattern,
+                equals,
+                value: Box::new(value),
+                depths,
+            }));
+        }
+
+        let expr = self.conditional()?;
 
         // Because assignment is right-associative
         if self.matches(&[TokenType::Equal]) {
             let equals = self.previous();
             let value = self.expression()?;
 
-            // Check if expr is a valid l-value (VariableExpr, aka identifier)
-            if let Expr::Variable(v) = expr {
-                return Ok(Expr::Assign(AssignExpr {
+            // Check if expr is a valid l-value (VariableExpr, aka identifier, or a property)
+            return match expr {
+                Expr::Variable(v) => Ok(Expr::Assign(AssignExpr {
                     name: v.name,
                     value: Box::new(value),
-                }));
-            }
-            self.error(equals, "Invalid assignment target.");
+                    depth: Cell::new(None),
+                })),
+                Expr::Get(g) => Ok(Expr::Set(SetExpr {
+                    object: g.object,
+                    name: g.name,
+                    value: Box::new(value),
+                })),
+                Expr::Index(i) => Ok(Expr::IndexSet(IndexSetExpr {
+                    object: i.object,
+                    bracket: i.bracket,
+                    index: i.index,
+                    value: Box::new(value),
+                })),
+                _ => {
+                    self.error(equals, "Invalid assignment target.");
+                    Ok(expr)
+                }
+            };
+        }
+
+        if self.matches(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let operator = self.previous();
+            let value = self.expression()?;
+            return Ok(self.compound(expr, operator, value, false));
+        }
+        Ok(expr)
+    }
+
+    /// Builds a compound assignment or increment, after checking that `target` can be assigned to
+    fn compound(&mut self, target: Expr, operator: Token, value: Expr, postfix: bool) -> Expr {
+        if !matches!(target, Expr::Variable(_) | Expr::Get(_) | Expr::Index(_)) {
+            self.error(operator, "Invalid assignment target.");
+            return target;
+        }
+
+        Expr::Compound(CompoundExpr {
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+            postfix,
+        })
+    }
+
+    fn one() -> Expr {
+        Expr::Literal(LiteralExpr {
+            value: Some(Lit::Num(1.0)),
+        })
+    }
+
+    /// `cond ? a : b`, where the else branch is parsed as another conditional, so that it nests
+    /// to the right
+    fn conditional(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.coalesce()?;
+
+        if self.matches(&[TokenType::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenType::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional(ConditionalExpr {
+                condition: Box::new(expr),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    fn coalesce(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.or()?;
+
+        while self.matches(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous();
+            let right = Box::new(self.or()?);
+            expr = Expr::Logical(LogicalExpr {
+                left: Box::new(expr),
+                operator,
+                right,
+            });
         }
         Ok(expr)
     }
@@ -287,7 +1015,7 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expr, LoxResult> {
-        let mut expr = self.term()?;
+        let mut expr = self.range()?;
 
         while self.matches(&[
             TokenType::Greater,
@@ -296,7 +1024,7 @@ impl Parser {
             TokenType::LessEqual,
         ]) {
             let operator = self.previous();
-            let right = Box::new(self.term()?);
+            let right = Box::new(self.range()?);
             expr = Expr::Binary(BinaryExpr {
                 left: Box::new(expr),
                 operator,
@@ -307,6 +1035,23 @@ impl Parser {
         Ok(expr)
     }
 
+    /// Ranges don't chain, `0..1..2` is a syntax error
+    fn range(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.term()?;
+
+        if self.matches(&[TokenType::DotDot]) {
+            let operator = self.previous();
+            let right = Box::new(self.term()?);
+            return Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr),
+                operator,
+                right,
+            }));
+        }
+
+        Ok(expr)
+    }
+
     fn term(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.factor()?;
 
@@ -326,7 +1071,12 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.unary()?;
 
-        while self.matches(&[TokenType::Slash, TokenType::Star]) {
+        while self.matches(&[
+            TokenType::Slash,
+            TokenType::Star,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous();
             let right = Box::new(self.unary()?);
             expr = Expr::Binary(BinaryExpr {
@@ -345,41 +1095,138 @@ impl Parser {
             let right = Box::new(self.unary()?);
             return Ok(Expr::Unary(UnaryExpr { operator, right }));
         }
-        self.call()
+        if self.matches(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            let target = self.unary()?;
+            return Ok(self.compound(target, operator, Self::one(), false));
+        }
+        self.power()
+    }
+
+    /// `**` binds tighter than unary operators on its left, so `-2 ** 2` is `-(2 ** 2)`, and is
+    /// right-associative by parsing its right operand as another unary expression
+    fn power(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.postfix()?;
+
+        if self.matches(&[TokenType::StarStar]) {
+            let operator = self.previous();
+            let right = Box::new(self.unary()?);
+            return Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr),
+                operator,
+                right,
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.call()?;
+
+        if self.matches(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous();
+            return Ok(self.compound(expr, operator, Self::one(), true));
+        }
+
+        Ok(expr)
     }
 
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxResult> {
-        let mut arguments = vec![];
+        let (arguments, paren) = self.arguments()?;
+        Ok(Expr::Call(CallExpr {
+            callee: Box::new(callee),
+            arguments,
+            paren,
+        }))
+    }
+
+    /// Parses the arguments of a call after its '(' and returns them with the closing ')'. Named
+    /// arguments have to come after the positional ones.
+    fn arguments(&mut self) -> Result<(Vec<Argument>, Token), LoxResult> {
+        let mut arguments: Vec<Argument> = vec![];
         if !self.check(TokenType::RightParen) {
-            arguments.push(self.expression()?);
-            while self.matches(&[TokenType::Comma]) {
+            loop {
                 if arguments.len() >= 255 {
                     self.error(self.peek(), "Can't have more than 255 arguments.");
+                }
+                let name = if self.check(TokenType::Identifier) && self.check_next(TokenType::Colon)
+                {
+                    let name = self.advance();
+                    self.advance();
+                    Some(name)
                 } else {
-                    arguments.push(self.expression()?);
+                    if arguments.iter().any(|argument| argument.name.is_some()) {
+                        self.error(
+                            self.peek(),
+                            "Positional arguments can't come after named ones.",
+                        );
+                    }
+                    None
+                };
+                let value = self.expression()?;
+                arguments.push(Argument { name, value });
+
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
                 }
             }
         }
 
         let paren = self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
-        Ok(Expr::Call(CallExpr {
-            callee: Box::new(callee),
-            arguments,
-            paren,
-        }))
+        Ok((arguments, paren))
     }
 
     fn call(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.primary()?;
+        let mut optional = false;
 
         loop {
             if self.matches(&[TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
+            } else if self.matches(&[TokenType::Dot]) {
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get(GetExpr {
+                    object: Box::new(expr),
+                    name,
+                });
+            } else if self.matches(&[TokenType::QuestionDot]) {
+                optional = true;
+                if self.matches(&[TokenType::LeftParen]) {
+                    let (arguments, paren) = self.arguments()?;
+                    expr = Expr::OptionalCall(OptionalCallExpr {
+                        callee: Box::new(expr),
+                        paren,
+                        arguments,
+                    });
+                } else {
+                    let name =
+                        self.consume(TokenType::Identifier, "Expect property name after '?.'.")?;
+                    expr = Expr::OptionalGet(OptionalGetExpr {
+                        object: Box::new(expr),
+                        name,
+                    });
+                }
+            } else if self.matches(&[TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index(IndexExpr {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                });
             } else {
                 break;
             }
         }
 
+        // A nil before any `?.` skips the rest of the chain, so the whole chain is wrapped up
+        if optional {
+            expr = Expr::Chain(ChainExpr {
+                expression: Box::new(expr),
+            });
+        }
         Ok(expr)
     }
 
@@ -404,12 +1251,79 @@ impl Parser {
             }));
         }
 
+        if self.matches(&[TokenType::Super]) {
+            let keyword = self.previous();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+            return Ok(Expr::Super(SuperExpr {
+                keyword,
+                method,
+                depth: Cell::new(None),
+            }));
+        }
+
+        if self.matches(&[TokenType::This]) {
+            return Ok(Expr::This(ThisExpr {
+                keyword: self.previous(),
+                depth: Cell::new(None),
+            }));
+        }
+
+        if self.matches(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+
         if self.matches(&[TokenType::Identifier]) {
             return Ok(Expr::Variable(VariableExpr {
                 name: self.previous(),
+                depth: Cell::new(None),
+            }));
+        }
+
+        if self.matches(&[TokenType::LeftBracket]) {
+            let mut elements = Vec::new();
+            if !self.check(TokenType::RightBracket) {
+                elements.push(self.expression()?);
+                while self.matches(&[TokenType::Comma]) {
+                    elements.push(self.expression()?);
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(ListExpr { elements }));
+        }
+
+        // Only reached in expression position, a '{' that starts a statement is a block
+        if self.matches(&[TokenType::LeftBrace]) {
+            let mut keys = Vec::new();
+            let mut values = Vec::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    keys.push(self.expression()?);
+                    self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                    values.push(self.expression()?);
+                    if !self.matches(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            let brace = self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map(MapExpr {
+                brace,
+                keys,
+                values,
             }));
         }
 
+        if self.matches(&[TokenType::Fun]) {
+            let name = Token::new(TokenType::Fun, "lambda", None, self.previous().line);
+            let declaration = self.function_body(name, "lambda")?;
+            return Ok(Expr::Lambda(LambdaExpr { declaration }));
+        }
+
+        if self.check(TokenType::LeftParen) && self.is_arrow_function() {
+            return self.arrow_function();
+        }
+
         if self.matches(&[TokenType::LeftParen]) {
             let expr = Box::new(self.expression()?);
             self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
@@ -419,6 +1333,33 @@ impl Parser {
         Err(LoxResult::parse_error(peek, "Expect expression."))
     }
 
+    /// Parses the rest of an interpolated string, whose first part was just matched.
+    /// The scanner emits the string parts as `Interpolation` tokens with the tokens of each
+    /// embedded expression in between, and the last part as a regular `String` token.
+    fn interpolation(&mut self) -> Result<Expr, LoxResult> {
+        let mut parts = vec![Expr::Literal(LiteralExpr {
+            value: self.previous().literal,
+        })];
+
+        loop {
+            parts.push(self.expression()?);
+            if self.matches(&[TokenType::Interpolation]) {
+                parts.push(Expr::Literal(LiteralExpr {
+                    value: self.previous().literal,
+                }));
+            } else {
+                let end = self.consume(
+                    TokenType::String,
+                    "Expect '}' after interpolated expression.",
+                )?;
+                parts.push(Expr::Literal(LiteralExpr { value: end.literal }));
+                break;
+            }
+        }
+
+        Ok(Expr::Interpolation(InterpolationExpr { parts }))
+    }
+
     fn consume(&mut self, tt: TokenType, message: &str) -> Result<Token, LoxResult> {
         if self.check(tt) {
             Ok(self.advance())
@@ -443,13 +1384,23 @@ impl Parser {
 
             match self.peek().ttype {
                 TokenType::Class
+                | TokenType::Const
+                | TokenType::Enum
+                | TokenType::Trait
+                | TokenType::Break
+                | TokenType::Continue
                 | TokenType::Fun
                 | TokenType::Var
                 | TokenType::For
                 | TokenType::If
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => {
+                | TokenType::Return
+                | TokenType::Import
+                | TokenType::Throw
+                | TokenType::Match
+                | TokenType::Yield
+                | TokenType::Try => {
                     return;
                 }
                 _ => {}
@@ -481,6 +1432,25 @@ impl Parser {
         }
     }
 
+    fn check_word(&self, word: &str) -> bool {
+        self.check(TokenType::Identifier) && self.peek().lexeme == word
+    }
+
+    fn consume_word(&mut self, word: &str, message: &str) -> Result<Token, LoxResult> {
+        if self.check_word(word) {
+            Ok(self.advance())
+        } else {
+            let p = self.peek();
+            Err(self.error(p, message))
+        }
+    }
+
+    fn check_next(&self, tt: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|t| t.ttype == tt)
+    }
+
     fn is_at_end(&self) -> bool {
         self.tokens[self.current].is(TokenType::Eof)
     }
@@ -0,0 +1,215 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::expr::Expr;
+use crate::lit::Lit;
+use crate::lox_map::MapKey;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+/// One `pattern if guard => body` arm of a match statement
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Stmt,
+}
+
+pub enum Pattern {
+    /// `_`, matches anything without binding it
+    Wildcard,
+    /// A literal that the value has to be equal to
+    Literal(Lit),
+    /// Matches anything and binds it to the name
+    Binding(Token),
+    /// `a | b`, matches if any of the alternatives does
+    Alternatives(Vec<Pattern>),
+    /// `start..end`, matches numbers from the start up to, but not including, the end
+    Range(f64, f64),
+    /// `[a, b, ...rest]`, matches lists of exactly as many elements unless there is a rest, which
+    /// gets a new list of the remaining ones
+    List {
+        elements: Vec<Pattern>,
+        rest: Option<Token>,
+    },
+    /// `{"key": pattern, name}`, matches maps that have all of the keys, whatever other keys they
+    /// have. A bare name is short for `"name": name`.
+    Map(Vec<(MapKey, Pattern)>),
+    /// `Enum.Variant(pattern, ...)`, matches values of the variant whose payload matches. The enum
+    /// is looked up like a variable, resolved to `depth` scopes up from the arm.
+    Variant {
+        enum_name: Token,
+        name: Token,
+        fields: Vec<Pattern>,
+        depth: Cell<Option<usize>>,
+    },
+}
+
+impl Pattern {
+    /// Checks `value` against the pattern, defining its bindings in `environment` along the way.
+    /// Bindings of a pattern that didn't match end up partially defined, so the environment should
+    /// be thrown away in that case.
+    pub fn matches(&self, value: &Lit, environment: &mut Environment) -> bool {
+        match self {
+            Pattern::Wildcard => true,
+            Pattern::Literal(literal) => value == literal,
+            Pattern::Binding(name) => {
+                environment.bind(&name.lexeme, value.clone());
+                true
+            }
+            Pattern::Alternatives(alternatives) => alternatives
+                .iter()
+                .any(|pattern| pattern.matches(value, environment)),
+            Pattern::Range(start, end) => {
+                matches!(value, Lit::Num(n) if start <= n && n < end)
+            }
+            Pattern::List { elements, rest } => {
+                let Lit::List(list) = value else {
+                    return false;
+                };
+                let list = list.borrow().clone();
+                let fits = if rest.is_some() {
+                    list.len() >= elements.len()
+                } else {
+                    list.len() == elements.len()
+                };
+                if !fits
+                    || !elements
+                        .iter()
+                        .zip(&list)
+                        .all(|(pattern, element)| pattern.matches(element, environment))
+                {
+                    return false;
+                }
+
+                if let Some(rest) = rest {
+                    let remaining = list[elements.len()..].to_vec();
+                    environment.bind(&rest.lexeme, Lit::List(Rc::new(RefCell::new(remaining))));
+                }
+                true
+            }
+            Pattern::Map(entries) => {
+                let Lit::Map(map) = value else {
+                    return false;
+                };
+                let map = map.borrow().clone();
+                entries.iter().all(|(key, pattern)| {
+                    map.get(key)
+                        .is_some_and(|value| pattern.matches(value, environment))
+                })
+            }
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                depth,
+            } => {
+                let Lit::Variant(variant) = value else {
+                    return false;
+                };
+                let lox_enum = match depth.get() {
+                    Some(distance) => environment.get_at(distance, enum_name),
+                    None => environment.get(enum_name),
+                };
+                let Ok(Lit::Enum(lox_enum)) = lox_enum else {
+                    return false;
+                };
+                variant.is(&lox_enum, &name.lexeme)
+                    && variant.values.len() == fields.len()
+                    && fields
+                        .iter()
+                        .zip(&variant.values)
+                        .all(|(pattern, value)| pattern.matches(value, environment))
+            }
+        }
+    }
+
+    /// Whether the pattern only takes values apart, which declarations, assignments and parameters
+    /// are limited to
+    pub fn is_destructuring(&self) -> bool {
+        match self {
+            Pattern::Wildcard | Pattern::Binding(_) => true,
+            Pattern::List { elements, .. } => elements.iter().all(Pattern::is_destructuring),
+            Pattern::Map(entries) => entries
+                .iter()
+                .all(|(_, pattern)| pattern.is_destructuring()),
+            Pattern::Literal(_)
+            | Pattern::Range(..)
+            | Pattern::Alternatives(_)
+            | Pattern::Variant { .. } => false,
+        }
+    }
+
+    /// Defines the bindings of a destructuring pattern in `environment`. Unlike matching, this
+    /// never fails on a missing element: list elements past the end and map keys that aren't there
+    /// are `None`, which binds nil to every name inside of them. Only a value of the wrong type is
+    /// an error, reported at `token`.
+    pub fn destructure(
+        &self,
+        value: Option<&Lit>,
+        environment: &mut Environment,
+        token: &Token,
+    ) -> Result<(), LoxResult> {
+        match (self, value) {
+            (Pattern::Binding(name), _) => {
+                environment.define(name, value.cloned().unwrap_or(Lit::Nil))?;
+            }
+            (Pattern::List { elements, rest }, Some(Lit::List(list))) => {
+                let list = list.borrow().clone();
+                for (i, element) in elements.iter().enumerate() {
+                    element.destructure(list.get(i), environment, token)?;
+                }
+                if let Some(rest) = rest {
+                    let remaining = list.get(elements.len()..).unwrap_or_default().to_vec();
+                    environment.define(rest, Lit::List(Rc::new(RefCell::new(remaining))))?;
+                }
+            }
+            (Pattern::Map(entries), Some(Lit::Map(map))) => {
+                let map = map.borrow().clone();
+                for (key, pattern) in entries {
+                    pattern.destructure(map.get(key), environment, token)?;
+                }
+            }
+            (Pattern::List { .. }, Some(value)) => {
+                return Err(LoxResult::runtime_error(
+                    token.clone(),
+                    &format!("Can only destructure a list with '[...]', not {}.", value),
+                ))
+            }
+            (Pattern::Map(_), Some(value)) => {
+                return Err(LoxResult::runtime_error(
+                    token.clone(),
+                    &format!("Can only destructure a map with '{{...}}', not {}.", value),
+                ))
+            }
+            _ => {
+                for name in self.bindings() {
+                    environment.define(name, Lit::Nil)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// All of the names that the pattern binds
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range(..) => Vec::new(),
+            Pattern::Binding(name) => vec![name],
+            Pattern::Alternatives(alternatives) => {
+                alternatives.iter().flat_map(Pattern::bindings).collect()
+            }
+            Pattern::List { elements, rest } => elements
+                .iter()
+                .flat_map(Pattern::bindings)
+                .chain(rest)
+                .collect(),
+            Pattern::Map(entries) => entries
+                .iter()
+                .flat_map(|(_, pattern)| pattern.bindings())
+                .collect(),
+            Pattern::Variant { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+        }
+    }
+}
@@ -0,0 +1,736 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::LoxResult;
+use crate::expr::*;
+use crate::pattern::Pattern;
+use crate::stmt::*;
+use crate::token::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+    Trait,
+    /// Inside of a static method, which has no `this`
+    Static,
+}
+
+/// Walks the syntax tree before it is interpreted and records, for every variable access,
+/// how many scopes away from the current one the variable was declared.
+pub struct Resolver {
+    /// The value is `true` once the variable's initializer has been resolved
+    scopes: Vec<HashMap<String, bool>>,
+    /// Names declared with `const`, for the global scope followed by one set for each of `scopes`
+    constants: Vec<HashSet<String>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    /// Number of loops around the current statement, within the current function
+    loop_depth: usize,
+    /// Number of try statements around the current statement, within the current function
+    try_depth: usize,
+    /// The variants of the enums declared so far by name, with how many fields each has, so that
+    /// misspelled variants are caught before running. Scoped like `constants`.
+    enums: Vec<HashMap<String, HashMap<String, usize>>>,
+    had_error: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StmtVisitor<()> for Resolver {
+    fn visit_block_stmt(&mut self, stmt: &BlockStmt) -> Result<(), LoxResult> {
+        self.begin_scope();
+        self.resolve(&stmt.statements)?;
+        self.end_scope();
+        Ok(())
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &ClassStmt) -> Result<(), LoxResult> {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        for name in &stmt.traits {
+            self.visit_variable_expr(name)?;
+        }
+
+        self.current_class = ClassType::Static;
+        for method in &stmt.statics {
+            self.resolve_function(method, FunctionType::Function)?;
+        }
+        self.current_class = ClassType::Class;
+
+        if let Some(superclass) = &stmt.superclass {
+            if superclass.name.lexeme == stmt.name.lexeme {
+                self.error(&superclass.name, "A class can't inherit from itself.");
+            }
+
+            self.current_class = ClassType::Subclass;
+            self.visit_variable_expr(superclass)?;
+
+            // Subclass methods close over an extra scope holding `super`
+            self.begin_scope();
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert("super".to_string(), true);
+            }
+        }
+
+        let methods = stmt
+            .methods
+            .iter()
+            .chain(&stmt.getters)
+            .chain(&stmt.setters);
+        let result = self.resolve_methods(methods);
+        if stmt.superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+        result
+    }
+
+    fn visit_trait_stmt(&mut self, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Trait;
+
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        let result = self.resolve_methods(&stmt.methods);
+
+        self.current_class = enclosing_class;
+        result
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &ExpressionStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.expression)
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &FunctionStmt) -> Result<(), LoxResult> {
+        // Declared and defined eagerly so that the function can refer to itself recursively
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        self.resolve_function(stmt, FunctionType::Function)
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.condition)?;
+        self.resolve_stmt(&stmt.then_branch)?;
+        if let Some(else_branch) = &stmt.else_branch {
+            self.resolve_stmt(else_branch)?;
+        }
+        Ok(())
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &PrintStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.expression)
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) -> Result<(), LoxResult> {
+        if self.current_function == FunctionType::None {
+            self.error(&stmt.keyword, "Can't return from top-level code.");
+        }
+
+        if let Some(value) = &stmt.value {
+            if self.current_function == FunctionType::Initializer {
+                self.error(&stmt.keyword, "Can't return a value from an initializer.");
+            }
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_enum_stmt(&mut self, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        let variants = stmt
+            .variants
+            .iter()
+            .map(|variant| (variant.name.lexeme.clone(), variant.fields.len()))
+            .collect();
+        if let Some(enums) = self.enums.last_mut() {
+            enums.insert(stmt.name.lexeme.clone(), variants);
+        }
+        Ok(())
+    }
+
+    fn visit_const_stmt(&mut self, stmt: &ConstStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name);
+        self.resolve_expr(&stmt.initializer)?;
+        self.define(&stmt.name);
+        if let Some(constants) = self.constants.last_mut() {
+            constants.insert(stmt.name.lexeme.clone());
+        }
+        Ok(())
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &ImportStmt) -> Result<(), LoxResult> {
+        for name in stmt.names.iter().chain(&stmt.alias) {
+            self.declare(name);
+            self.define(name);
+        }
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &ThrowStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.value)
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &TryStmt) -> Result<(), LoxResult> {
+        self.try_depth += 1;
+        let result = self.resolve_try(stmt);
+        self.try_depth -= 1;
+        result
+    }
+
+    fn visit_match_stmt(&mut self, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.subject)?;
+        for arm in &stmt.arms {
+            self.begin_scope();
+            self.declare_pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                self.resolve_expr(guard)?;
+            }
+            self.resolve_stmt(&arm.body)?;
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    fn visit_yield_stmt(&mut self, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        match self.current_function {
+            FunctionType::None => self.error(&stmt.keyword, "Can't yield from top-level code."),
+            FunctionType::Initializer => {
+                self.error(&stmt.keyword, "Can't yield from an initializer.")
+            }
+            // Generators can't suspend in the middle of a try statement
+            _ if self.try_depth > 0 => {
+                self.error(&stmt.keyword, "Can't yield inside a 'try' statement.")
+            }
+            _ => {}
+        }
+
+        if let Some(value) = &stmt.value {
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_destructure_stmt(&mut self, stmt: &DestructureStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.initializer)?;
+        self.declare_pattern(&stmt.pattern);
+        Ok(())
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &VarStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name);
+        if let Some(init) = &stmt.initializer {
+            self.resolve_expr(init)?;
+        }
+        self.define(&stmt.name);
+        Ok(())
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &WhileStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.condition)?;
+
+        self.loop_depth += 1;
+        let result = self.resolve_stmt(&stmt.body);
+        self.loop_depth -= 1;
+        result?;
+
+        if let Some(increment) = &stmt.increment {
+            self.resolve_expr(increment)?;
+        }
+        Ok(())
+    }
+
+    fn visit_forin_stmt(&mut self, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(&stmt.iterable)?;
+
+        // Every iteration gets a scope with just the loop variable in it
+        self.begin_scope();
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        self.loop_depth += 1;
+        let result = self.resolve_stmt(&stmt.body);
+        self.loop_depth -= 1;
+        self.end_scope();
+        result
+    }
+
+    fn visit_break_stmt(&mut self, stmt: &BreakStmt) -> Result<(), LoxResult> {
+        if self.loop_depth == 0 {
+            self.error(&stmt.keyword, "Can't use 'break' outside of a loop.");
+        }
+        Ok(())
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &ContinueStmt) -> Result<(), LoxResult> {
+        if self.loop_depth == 0 {
+            self.error(&stmt.keyword, "Can't use 'continue' outside of a loop.");
+        }
+        Ok(())
+    }
+}
+
+impl ExprVisitor<()> for Resolver {
+    fn visit_assign_expr(&mut self, expr: &AssignExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        self.check_mutable(&expr.name);
+        self.resolve_local(&expr.depth, &expr.name);
+        Ok(())
+    }
+
+    fn visit_binary_expr(&mut self, expr: &BinaryExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.left)?;
+        self.resolve_expr(&expr.right)
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.callee)?;
+        for argument in &expr.arguments {
+            self.resolve_expr(&argument.value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_compound_expr(&mut self, expr: &CompoundExpr) -> Result<(), LoxResult> {
+        if let Expr::Variable(target) = expr.target.as_ref() {
+            self.check_mutable(&target.name);
+        }
+        self.resolve_expr(&expr.target)?;
+        self.resolve_expr(&expr.value)
+    }
+
+    fn visit_conditional_expr(&mut self, expr: &ConditionalExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.condition)?;
+        self.resolve_expr(&expr.then_branch)?;
+        self.resolve_expr(&expr.else_branch)
+    }
+
+    fn visit_destructure_expr(&mut self, expr: &DestructureExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        for (name, depth) in expr.pattern.bindings().into_iter().zip(&expr.depths) {
+            self.check_mutable(name);
+            self.resolve_local(depth, name);
+        }
+        Ok(())
+    }
+
+    fn visit_chain_expr(&mut self, expr: &ChainExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.expression)
+    }
+
+    fn visit_optionalcall_expr(&mut self, expr: &OptionalCallExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.callee)?;
+        for argument in &expr.arguments {
+            self.resolve_expr(&argument.value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_optionalget_expr(&mut self, expr: &OptionalGetExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.object)
+    }
+
+    fn visit_get_expr(&mut self, expr: &GetExpr) -> Result<(), LoxResult> {
+        // Properties are looked up dynamically, so only the object gets resolved
+        if let Expr::Variable(object) = expr.object.as_ref() {
+            self.check_variant(&object.name, &expr.name, None);
+        }
+        self.resolve_expr(&expr.object)
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &GroupingExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.expression)
+    }
+
+    fn visit_index_expr(&mut self, expr: &IndexExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.object)?;
+        self.resolve_expr(&expr.index)
+    }
+
+    fn visit_indexset_expr(&mut self, expr: &IndexSetExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        self.resolve_expr(&expr.object)?;
+        self.resolve_expr(&expr.index)
+    }
+
+    fn visit_list_expr(&mut self, expr: &ListExpr) -> Result<(), LoxResult> {
+        for element in &expr.elements {
+            self.resolve_expr(element)?;
+        }
+        Ok(())
+    }
+
+    fn visit_map_expr(&mut self, expr: &MapExpr) -> Result<(), LoxResult> {
+        for (key, value) in expr.keys.iter().zip(&expr.values) {
+            self.resolve_expr(key)?;
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr) -> Result<(), LoxResult> {
+        self.resolve_function(&expr.declaration, FunctionType::Function)
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &InterpolationExpr) -> Result<(), LoxResult> {
+        for part in &expr.parts {
+            self.resolve_expr(part)?;
+        }
+        Ok(())
+    }
+
+    fn visit_literal_expr(&mut self, _expr: &LiteralExpr) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_logical_expr(&mut self, expr: &LogicalExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.left)?;
+        self.resolve_expr(&expr.right)
+    }
+
+    fn visit_set_expr(&mut self, expr: &SetExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.value)?;
+        self.resolve_expr(&expr.object)
+    }
+
+    fn visit_super_expr(&mut self, expr: &SuperExpr) -> Result<(), LoxResult> {
+        match self.current_class {
+            ClassType::None => {
+                self.error(&expr.keyword, "Can't use 'super' outside of a class.");
+            }
+            ClassType::Class => {
+                self.error(
+                    &expr.keyword,
+                    "Can't use 'super' in a class with no superclass.",
+                );
+            }
+            ClassType::Trait => {
+                self.error(&expr.keyword, "Can't use 'super' in a trait.");
+            }
+            ClassType::Static => {
+                self.error(&expr.keyword, "Can't use 'super' in a static method.");
+            }
+            ClassType::Subclass => {
+                self.resolve_local(&expr.depth, &expr.keyword);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_this_expr(&mut self, expr: &ThisExpr) -> Result<(), LoxResult> {
+        match self.current_class {
+            ClassType::None => {
+                self.error(&expr.keyword, "Can't use 'this' outside of a class.");
+                return Ok(());
+            }
+            ClassType::Static => {
+                self.error(&expr.keyword, "Can't use 'this' in a static method.");
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        self.resolve_local(&expr.depth, &expr.keyword);
+        Ok(())
+    }
+
+    fn visit_unary_expr(&mut self, expr: &UnaryExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(&expr.right)
+    }
+
+    fn visit_variable_expr(&mut self, expr: &VariableExpr) -> Result<(), LoxResult> {
+        if let Some(scope) = self.scopes.last() {
+            if scope.get(&expr.name.lexeme) == Some(&false) {
+                self.error(
+                    &expr.name,
+                    "Can't read local variable in its own initializer.",
+                );
+            }
+        }
+
+        self.resolve_local(&expr.depth, &expr.name);
+        Ok(())
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            constants: vec![HashSet::new()],
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            loop_depth: 0,
+            try_depth: 0,
+            enums: vec![HashMap::new()],
+            had_error: false,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), LoxResult> {
+        for statement in statements {
+            self.resolve_stmt(statement)?;
+        }
+        Ok(())
+    }
+
+    pub fn success(&self) -> bool {
+        !self.had_error
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<(), LoxResult> {
+        stmt.accept(self)
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) -> Result<(), LoxResult> {
+        expr.accept(self)
+    }
+
+    /// Resolves the methods of a class or trait, which close over a scope that only holds `this`
+    fn resolve_methods<'a>(
+        &mut self,
+        methods: impl IntoIterator<Item = &'a FunctionStmt>,
+    ) -> Result<(), LoxResult> {
+        self.begin_scope();
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert("this".to_string(), true);
+        }
+
+        let mut result = Ok(());
+        for method in methods {
+            let declaration = if method.name.lexeme == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            result = self.resolve_function(method, declaration);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.end_scope();
+        result
+    }
+
+    fn resolve_function(
+        &mut self,
+        function: &FunctionStmt,
+        ftype: FunctionType,
+    ) -> Result<(), LoxResult> {
+        let enclosing_function = self.current_function;
+        self.current_function = ftype;
+        // Loops outside of the function can't be broken out of from inside it
+        let enclosing_loop_depth = self.loop_depth;
+        self.loop_depth = 0;
+        let enclosing_try_depth = self.try_depth;
+        self.try_depth = 0;
+
+        self.begin_scope();
+        // Default values are evaluated in the function's scope, after the parameters before them
+        let mut result = Ok(());
+        for param in function.params.iter() {
+            if let Some(default) = &param.default {
+                result = result.and(self.resolve_expr(default));
+            }
+            match &param.pattern {
+                Some(pattern) => self.declare_pattern(pattern),
+                None => {
+                    self.declare(&param.name);
+                    self.define(&param.name);
+                }
+            }
+        }
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+            self.define(rest);
+        }
+        let result = result.and(self.resolve(&function.body));
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+        self.try_depth = enclosing_try_depth;
+        result
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.constants.push(HashSet::new());
+        self.enums.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+        self.constants.pop();
+        self.enums.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&name.lexeme) {
+                self.error(name, "Already a variable with this name in this scope.");
+            } else {
+                scope.insert(name.lexeme.clone(), false);
+            }
+        } else if self.constants[0].contains(&name.lexeme) {
+            // Other globals can be redeclared, but that would make a constant mutable again
+            self.error(name, "Can't redeclare a constant.");
+        }
+        // A global enum that gets redeclared as something else is no longer checked
+        if let Some(enums) = self.enums.last_mut() {
+            enums.remove(&name.lexeme);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding(name) => {
+                self.declare(name);
+                self.define(name);
+            }
+            // Only some of the alternatives match, so their names might not be bound
+            Pattern::Alternatives(alternatives) => {
+                for alternative in alternatives {
+                    if let Some(name) = alternative.bindings().first() {
+                        self.error(name, "Can't bind names inside of '|' alternatives.");
+                    }
+                }
+            }
+            Pattern::List { elements, rest } => {
+                for element in elements {
+                    self.declare_pattern(element);
+                }
+                if let Some(rest) = rest {
+                    self.declare(rest);
+                    self.define(rest);
+                }
+            }
+            Pattern::Map(entries) => {
+                for (_, pattern) in entries {
+                    self.declare_pattern(pattern);
+                }
+            }
+            Pattern::Variant {
+                enum_name,
+                name,
+                fields,
+                depth,
+            } => {
+                self.check_variant(enum_name, name, Some(fields.len()));
+                self.resolve_local(depth, enum_name);
+                for pattern in fields {
+                    self.declare_pattern(pattern);
+                }
+            }
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Range(..) => {}
+        }
+    }
+
+    /// Checks that `enum_name.name` is a variant, with `fields` fields if given. Names that aren't
+    /// known to be enums where they are declared, like the ones imported from other modules or
+    /// shadowed by a variable, are left to runtime.
+    fn check_variant(&mut self, enum_name: &Token, name: &Token, fields: Option<usize>) {
+        let level = self.scope_level(enum_name);
+        let Some(variants) = self.enums[level].get(&enum_name.lexeme) else {
+            return;
+        };
+        match (variants.get(&name.lexeme), fields) {
+            (None, _) => {
+                let message = format!(
+                    "Enum '{}' has no variant '{}'.",
+                    enum_name.lexeme, name.lexeme
+                );
+                self.error(name, &message);
+            }
+            (Some(&expected), Some(fields)) if expected != fields => {
+                let message = format!(
+                    "Variant '{}.{}' has {} fields, but the pattern has {}.",
+                    enum_name.lexeme, name.lexeme, expected, fields
+                );
+                self.error(name, &message);
+            }
+            _ => {}
+        }
+    }
+
+    fn resolve_try(&mut self, stmt: &TryStmt) -> Result<(), LoxResult> {
+        self.begin_scope();
+        self.resolve(&stmt.body)?;
+        self.end_scope();
+
+        if let Some(catch_body) = &stmt.catch_body {
+            self.begin_scope();
+            if let Some(name) = &stmt.catch_name {
+                self.declare(name);
+                self.define(name);
+            }
+            self.resolve(catch_body)?;
+            self.end_scope();
+        }
+
+        if let Some(finally_body) = &stmt.finally_body {
+            self.begin_scope();
+            self.resolve(finally_body)?;
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    /// Reports assignments to constants that are known statically. Global constants that were
+    /// declared by an earlier run, or after the assignment, are caught at runtime instead.
+    fn check_mutable(&mut self, name: &Token) {
+        let level = self.scope_level(name);
+        if self.constants[level].contains(&name.lexeme) {
+            self.error(name, "Can't assign to a constant.");
+        }
+    }
+
+    /// Where `name` is declared, as an index into `constants` and `enums`, which is 0 for globals
+    fn scope_level(&self, name: &Token) -> usize {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(&name.lexeme))
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Variables that aren't found in any scope are left unresolved and assumed to be global
+    fn resolve_local(&mut self, depth: &Cell<Option<usize>>, name: &Token) {
+        for (i, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                depth.set(Some(i));
+                return;
+            }
+        }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.had_error = true;
+        LoxResult::parse_error(token.clone(), message);
+    }
+}
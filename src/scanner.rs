@@ -1,21 +1,29 @@
 use crate::{error::LoxResult, lit::*, token::Token, token_type::*};
 
 pub struct Scanner {
-    source: String,
+    /// Kept as characters so that `start` and `current` index characters rather than bytes
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    /// Index of the first character of the current line, used to work out columns
+    line_start: usize,
+    /// One entry per string interpolation that is currently open, counting the unclosed '{'
+    /// inside of it so that the matching '}' resumes the string
+    interpolations: Vec<usize>,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
         Self {
-            source,
+            source: source.chars().collect(),
             tokens: vec![],
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            interpolations: Vec::new(),
         }
     }
 
@@ -32,6 +40,13 @@ impl Scanner {
             }
         }
 
+        if !self.interpolations.is_empty() {
+            had_error = Some(LoxResult::error(
+                self.line,
+                "Unterminated string interpolation.",
+            ));
+        }
+
         self.tokens.push(Token::eof(self.line));
 
         if let Some(err) = had_error {
@@ -51,14 +66,85 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => match self.interpolations.last_mut() {
+                // Closes the interpolated expression, the rest is more of the string
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string()?;
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace);
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            ':' => self.add_token(TokenType::Colon),
+            '?' => {
+                let tok = if self.matches('?') {
+                    TokenType::QuestionQuestion
+                } else if self.matches('.') {
+                    TokenType::QuestionDot
+                } else {
+                    TokenType::Question
+                };
+                self.add_token(tok);
+            }
+            '.' => {
+                let tok = if self.matches('.') {
+                    if self.matches('.') {
+                        TokenType::DotDotDot
+                    } else {
+                        TokenType::DotDot
+                    }
+                } else {
+                    TokenType::Dot
+                };
+                self.add_token(tok);
+            }
+            '-' => {
+                let tok = if self.matches('-') {
+                    TokenType::MinusMinus
+                } else if self.matches('=') {
+                    TokenType::MinusEqual
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(tok);
+            }
+            '+' => {
+                let tok = if self.matches('+') {
+                    TokenType::PlusPlus
+                } else if self.matches('=') {
+                    TokenType::PlusEqual
+                } else {
+                    TokenType::Plus
+                };
+                self.add_token(tok);
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
+            '*' => {
+                let tok = if self.matches('*') {
+                    TokenType::StarStar
+                } else if self.matches('=') {
+                    TokenType::StarEqual
+                } else {
+                    TokenType::Star
+                };
+                self.add_token(tok);
+            }
+            '%' => self.add_token(TokenType::Percent),
+            '|' => self.add_token(TokenType::Pipe),
+            // Floor division, since `//` already starts a comment
+            '~' if self.matches('/') => self.add_token(TokenType::TildeSlash),
             '!' => {
                 let tok = if self.matches('=') {
                     TokenType::BangEqual
@@ -70,6 +156,8 @@ impl Scanner {
             '=' => {
                 let tok = if self.matches('=') {
                     TokenType::EqualEqual
+                } else if self.matches('>') {
+                    TokenType::EqualGreater
                 } else {
                     TokenType::Equal
                 };
@@ -103,21 +191,23 @@ impl Scanner {
                 } else if self.matches('*') {
                     // block comment start
                     self.scan_comment()?;
+                } else if self.matches('=') {
+                    self.add_token(TokenType::SlashEqual)
                 } else {
                     self.add_token(TokenType::Slash)
                 }
             }
             ' ' | '\r' | '\t' => {}
             '\n' => {
-                self.line += 1;
+                self.new_line();
             }
             '"' => {
                 self.string()?;
             }
-            c if c.is_numeric() => {
+            c if c.is_ascii_digit() => {
                 self.number()?;
             }
-            c if c.is_alphabetic() => {
+            c if c.is_alphabetic() || c == '_' => {
                 self.identifier()?;
             }
             _ => {
@@ -143,7 +233,8 @@ impl Scanner {
                     }
                 }
                 Some('\n') => {
-                    self.line += 1;
+                    self.advance();
+                    self.new_line();
                 }
                 None => {
                     return Err(LoxResult::error(
@@ -158,6 +249,17 @@ impl Scanner {
         }
     }
 
+    /// Must be called right after advancing past a '\n'
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
+    /// 1-based column of the next character
+    fn column(&self) -> usize {
+        self.current - self.line_start + 1
+    }
+
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
@@ -177,13 +279,18 @@ impl Scanner {
     }
 
     fn add_token_lit(&mut self, ttype: TokenType, lit: Option<Lit>) {
-        let lexeme = &self.source[self.start..self.current];
-        self.tokens.push(Token::new(ttype, lexeme, lit, self.line))
+        let lexeme = self.lexeme();
+        self.tokens.push(Token::new(ttype, &lexeme, lit, self.line))
+    }
+
+    /// The source of the token being scanned
+    fn lexeme(&self) -> String {
+        self.source[self.start..self.current].iter().collect()
     }
 
     /// If the next character matches the expected character, advance to it and then return true.
     fn matches(&mut self, expected: char) -> bool {
-        if self.peek().map_or(false, |cur| cur == expected) {
+        if self.peek().is_some_and(|cur| cur == expected) {
             self.current += 1;
             return true;
         }
@@ -192,47 +299,148 @@ impl Scanner {
 
     /// Peeks at the next character. Returns `None` on the end of source
     pub fn peek(&self) -> Option<char> {
-        if self.is_at_end() {
-            return None;
-        }
-        Some(self.source.chars().nth(self.current).unwrap())
+        self.source.get(self.current).copied()
     }
 
     /// Peeks at the character after the next character. Returns `None` on the end of source
     pub fn peek_next(&self) -> Option<char> {
-        if self.is_at_end() {
-            return None;
-        }
-        Some(self.source.chars().nth(self.current + 1).unwrap())
+        self.source.get(self.current + 1).copied()
     }
 
+    /// Scans a string up to its closing quote, or up to the start of an interpolated `${expr}`.
+    /// In the latter case the part so far becomes an `Interpolation` token, and the string is
+    /// picked up again once the matching '}' is scanned.
     pub fn string(&mut self) -> Result<(), LoxResult> {
-        while let Some(pk) = self.peek() {
-            match pk {
-                '"' => break,
-                '\n' => self.line += 1,
-                _ => {}
+        let mut value = String::new();
+        // Keep scanning to the closing quote after a bad escape so the rest of the string
+        // doesn't get scanned as code
+        let mut error = None;
+
+        loop {
+            match self.bump() {
+                None => return Err(LoxResult::error(self.line, "Unterminated String.")),
+                Some('"') => break,
+                Some('$') if self.matches('{') => {
+                    if let Some(err) = error {
+                        return Err(err);
+                    }
+                    self.interpolations.push(0);
+                    self.add_token_lit(TokenType::Interpolation, Some(Lit::Str(value)));
+                    return Ok(());
+                }
+                Some('\\') => match self.escape() {
+                    Ok(c) => value.push(c),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                },
+                Some('\n') => {
+                    self.new_line();
+                    value.push('\n');
+                }
+                Some(c) => value.push(c),
             }
-            self.advance();
         }
-        // If not at the end, then guranteed next to to be '"'
-        if self.is_at_end() {
-            return Err(LoxResult::error(self.line, "Unterminated String."));
+
+        if let Some(err) = error {
+            return Err(err);
         }
-        // TODO: Handle escape sequences such ads "\\" or "\n" etc.
-        self.advance();
-        let value = String::from(&self.source[self.start + 1..self.current - 1]);
         self.add_token_lit(TokenType::String, Some(Lit::Str(value)));
         Ok(())
     }
 
+    /// Scans the rest of an escape sequence, the backslash has already been consumed
+    fn escape(&mut self) -> Result<char, LoxResult> {
+        let column = self.column() - 1;
+        match self.peek() {
+            Some('n') => {
+                self.advance();
+                Ok('\n')
+            }
+            Some('t') => {
+                self.advance();
+                Ok('\t')
+            }
+            Some('r') => {
+                self.advance();
+                Ok('\r')
+            }
+            Some('0') => {
+                self.advance();
+                Ok('\0')
+            }
+            Some(c @ ('\\' | '"' | '$')) => {
+                self.advance();
+                Ok(c)
+            }
+            Some('u') => {
+                self.advance();
+                self.unicode_escape(column)
+            }
+            // Leave the character alone, it could be the closing quote or a newline
+            Some(c) => Err(LoxResult::scan_error(
+                self.line,
+                column,
+                &format!("Unknown escape sequence '\\{}'.", c.escape_default()),
+            )),
+            None => Err(LoxResult::scan_error(
+                self.line,
+                column,
+                "Unterminated escape sequence.",
+            )),
+        }
+    }
+
+    /// Scans the `{XXXX}` part of a `\u{XXXX}` escape, with between one and six hex digits
+    fn unicode_escape(&mut self, column: usize) -> Result<char, LoxResult> {
+        if !self.matches('{') {
+            return Err(LoxResult::scan_error(
+                self.line,
+                column,
+                "Expect '{' after '\\u'.",
+            ));
+        }
+
+        let mut digits = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_hexdigit() {
+                break;
+            }
+            digits.push(c);
+            self.advance();
+        }
+
+        if !self.matches('}') {
+            return Err(LoxResult::scan_error(
+                self.line,
+                column,
+                "Expect hex digits followed by '}' in unicode escape.",
+            ));
+        }
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(LoxResult::scan_error(
+                self.line,
+                column,
+                "Unicode escape must have between one and six hex digits.",
+            ));
+        }
+
+        let code = u32::from_str_radix(&digits, 16).unwrap();
+        char::from_u32(code).ok_or_else(|| {
+            LoxResult::scan_error(
+                self.line,
+                column,
+                &format!("Invalid unicode code point U+{:X}.", code),
+            )
+        })
+    }
+
     fn identifier(&mut self) -> Result<(), LoxResult> {
-        while self.peek().map_or(false, |c| c.is_alphanumeric()) {
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
             self.advance();
         }
 
-        let value = &self.source[self.start..self.current];
-        if let Some(keyword) = Self::keywords(value) {
+        if let Some(keyword) = Self::keywords(&self.lexeme()) {
             self.add_token(keyword);
         } else {
             self.add_token(TokenType::Identifier);
@@ -244,7 +452,7 @@ impl Scanner {
     pub fn number(&mut self) -> Result<(), LoxResult> {
         while let Some(c) = self.peek() {
             match c {
-                c if c.is_numeric() => {
+                c if c.is_ascii_digit() => {
                     self.advance();
                     continue;
                 }
@@ -255,10 +463,10 @@ impl Scanner {
         }
 
         if let Some(c) = self.peek() {
-            if c == '.' && self.peek_next().map_or(false, |pk| pk.is_numeric()) {
+            if c == '.' && self.peek_next().is_some_and(|pk| pk.is_ascii_digit()) {
                 self.advance();
 
-                while self.peek().map_or(false, |pk| pk.is_numeric()) {
+                while self.peek().is_some_and(|pk| pk.is_ascii_digit()) {
                     self.advance();
                 }
             }
@@ -266,9 +474,7 @@ impl Scanner {
 
         self.add_token_lit(
             TokenType::Number,
-            Some(Lit::Num(
-                (&self.source[self.start..self.current]).parse().unwrap(),
-            )),
+            Some(Lit::Num(self.lexeme().parse().unwrap())),
         );
         Ok(())
     }
@@ -276,21 +482,34 @@ impl Scanner {
     pub fn keywords(s: &str) -> Option<TokenType> {
         match s {
             "and" => Some(TokenType::And),
+            "break" => Some(TokenType::Break),
+            "catch" => Some(TokenType::Catch),
             "class" => Some(TokenType::Class),
+            "const" => Some(TokenType::Const),
+            "continue" => Some(TokenType::Continue),
             "else" => Some(TokenType::Else),
+            "enum" => Some(TokenType::Enum),
             "false" => Some(TokenType::False),
+            "finally" => Some(TokenType::Finally),
             "for" => Some(TokenType::For),
             "fun" => Some(TokenType::Fun),
             "if" => Some(TokenType::If),
+            "import" => Some(TokenType::Import),
+            "in" => Some(TokenType::In),
+            "match" => Some(TokenType::Match),
             "nil" => Some(TokenType::Nil),
             "or" => Some(TokenType::Or),
             "print" => Some(TokenType::Print),
             "return" => Some(TokenType::Return),
             "super" => Some(TokenType::Super),
             "this" => Some(TokenType::This),
+            "throw" => Some(TokenType::Throw),
+            "trait" => Some(TokenType::Trait),
             "true" => Some(TokenType::True),
+            "try" => Some(TokenType::Try),
             "var" => Some(TokenType::Var),
             "while" => Some(TokenType::While),
+            "yield" => Some(TokenType::Yield),
             _ => None,
         }
     }
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::LoxResult;
+use crate::expr::Expr;
+use crate::lit::Lit;
+use crate::pattern::Pattern;
+use crate::token::Token;
+
+/// One parameter of a function declaration, `name` or `name = default`. A parameter can also be
+/// a list or map pattern that takes its argument apart, in which case `name` is only made up to
+/// refer to it in errors.
+pub struct Parameter {
+    pub name: Token,
+    pub pattern: Option<Pattern>,
+    pub default: Option<Expr>,
+}
+
+/// One argument of a call, `value` or `name: value`
+pub struct Argument {
+    pub name: Option<Token>,
+    pub value: Expr,
+}
+
+/// The parameters that a callable takes, which the arguments of a call are checked against.
+/// Between `required` and the number of parameters, plus any number more with a rest parameter.
+pub struct Signature {
+    pub params: Vec<String>,
+    /// How many of the leading parameters have no default
+    pub required: usize,
+    /// The `...rest` parameter, which gets a list of the positional arguments left over
+    pub rest: Option<String>,
+}
+
+impl Signature {
+    /// A signature where every parameter is required
+    pub fn new(params: &[&str]) -> Self {
+        Self {
+            params: params.iter().map(|param| param.to_string()).collect(),
+            required: params.len(),
+            rest: None,
+        }
+    }
+
+    /// Matches the arguments of a call up with the parameters. Returns a value for each parameter
+    /// in order, with `None` for the optional ones that weren't passed, followed by a list of the
+    /// leftover positional arguments if there is a rest parameter.
+    pub fn bind(
+        &self,
+        positional: Vec<Lit>,
+        named: Vec<(Token, Lit)>,
+        paren: &Token,
+    ) -> Result<Vec<Option<Lit>>, LoxResult> {
+        let given = positional.len();
+        let mut values: Vec<Option<Lit>> = vec![None; self.params.len()];
+        let mut rest = Vec::new();
+        for (i, value) in positional.into_iter().enumerate() {
+            if i < values.len() {
+                values[i] = Some(value);
+            } else if self.rest.is_some() {
+                rest.push(value);
+            } else {
+                return Err(LoxResult::runtime_error(
+                    paren.clone(),
+                    &format!(
+                        "Expected at most {} argument{}, but got {}.",
+                        self.params.len(),
+                        if self.params.len() == 1 { "" } else { "s" },
+                        given
+                    ),
+                ));
+            }
+        }
+
+        for (name, value) in named {
+            match self.params.iter().position(|param| *param == name.lexeme) {
+                Some(i) if values[i].is_none() => values[i] = Some(value),
+                Some(_) => {
+                    return Err(LoxResult::runtime_error(
+                        name.clone(),
+                        &format!("Parameter '{}' was given more than once.", name.lexeme),
+                    ))
+                }
+                None => {
+                    return Err(LoxResult::runtime_error(
+                        name.clone(),
+                        &format!("Unexpected argument '{}'.", name.lexeme),
+                    ))
+                }
+            }
+        }
+
+        if let Some(missing) = (0..self.required).find(|&i| values[i].is_none()) {
+            return Err(LoxResult::runtime_error(
+                paren.clone(),
+                &format!("Missing argument for parameter '{}'.", self.params[missing]),
+            ));
+        }
+
+        if self.rest.is_some() {
+            values.push(Some(Lit::List(Rc::new(RefCell::new(rest)))));
+        }
+        Ok(values)
+    }
+}
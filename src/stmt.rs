@@ -1,14 +1,32 @@
 use crate::error::*;
 use crate::token::*;
 use crate::expr::*;
+use crate::lox_enum::*;
+use crate::pattern::*;
+use crate::signature::*;
+use std::rc::Rc;
 
 pub enum Stmt {
     Block(BlockStmt),
+    Break(BreakStmt),
+    Class(ClassStmt),
+    Const(ConstStmt),
+    Continue(ContinueStmt),
+    Destructure(DestructureStmt),
+    Enum(EnumStmt),
     Expression(ExpressionStmt),
+    ForIn(ForInStmt),
     Function(FunctionStmt),
+    Import(ImportStmt),
     If(IfStmt),
+    Match(MatchStmt),
     Print(PrintStmt),
+    Return(ReturnStmt),
+    Throw(ThrowStmt),
+    Trait(TraitStmt),
+    Try(TryStmt),
     Var(VarStmt),
+    Yield(YieldStmt),
     While(WhileStmt),
 }
 
@@ -18,21 +36,63 @@ impl Stmt {
             Stmt::Block(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::Break(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Class(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Const(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Continue(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Destructure(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Enum(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::Expression(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::ForIn(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::Function(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::Import(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::If(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::Match(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::Print(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::Return(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Throw(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Trait(stmt) => {
+                stmt.accept(visitor)
+            }
+            Stmt::Try(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::Var(stmt) => {
                 stmt.accept(visitor)
             }
+            Stmt::Yield(stmt) => {
+                stmt.accept(visitor)
+            }
             Stmt::While(stmt) => {
                 stmt.accept(visitor)
             }
@@ -44,14 +104,62 @@ pub struct BlockStmt {
     pub statements: Vec<Stmt>,
 }
 
+pub struct BreakStmt {
+    pub keyword: Token,
+}
+
+pub struct ClassStmt {
+    pub name: Token,
+    pub superclass: Option<VariableExpr>,
+    pub traits: Vec<VariableExpr>,
+    pub methods: Vec<FunctionStmt>,
+    pub getters: Vec<FunctionStmt>,
+    pub setters: Vec<FunctionStmt>,
+    pub statics: Vec<FunctionStmt>,
+}
+
+pub struct ConstStmt {
+    pub name: Token,
+    pub initializer: Expr,
+}
+
+pub struct ContinueStmt {
+    pub keyword: Token,
+}
+
+pub struct DestructureStmt {
+    pub pattern: Pattern,
+    pub equals: Token,
+    pub initializer: Expr,
+}
+
+pub struct EnumStmt {
+    pub name: Token,
+    pub variants: Vec<Variant>,
+}
+
 pub struct ExpressionStmt {
     pub expression: Expr,
 }
 
+pub struct ForInStmt {
+    pub name: Token,
+    pub iterable: Expr,
+    pub body: Box<Stmt>,
+}
+
 pub struct FunctionStmt {
     pub name: Token,
-    pub params: Vec<Token>,
-    pub body: Vec<Stmt>,
+    pub params: Rc<Vec<Parameter>>,
+    pub rest: Option<Token>,
+    pub body: Rc<Vec<Stmt>>,
+}
+
+pub struct ImportStmt {
+    pub keyword: Token,
+    pub path: Token,
+    pub alias: Option<Token>,
+    pub names: Vec<Token>,
 }
 
 pub struct IfStmt {
@@ -60,27 +168,75 @@ pub struct IfStmt {
     pub else_branch: Option<Box<Stmt>>,
 }
 
+pub struct MatchStmt {
+    pub keyword: Token,
+    pub subject: Expr,
+    pub arms: Vec<MatchArm>,
+}
+
 pub struct PrintStmt {
     pub expression: Expr,
 }
 
+pub struct ReturnStmt {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+}
+
+pub struct ThrowStmt {
+    pub keyword: Token,
+    pub value: Expr,
+}
+
+pub struct TraitStmt {
+    pub name: Token,
+    pub methods: Vec<FunctionStmt>,
+}
+
+pub struct TryStmt {
+    pub body: Vec<Stmt>,
+    pub catch_name: Option<Token>,
+    pub catch_body: Option<Vec<Stmt>>,
+    pub finally_body: Option<Vec<Stmt>>,
+}
+
 pub struct VarStmt {
     pub name: Token,
     pub initializer: Option<Expr>,
 }
 
+pub struct YieldStmt {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+}
+
 pub struct WhileStmt {
     pub condition: Expr,
     pub body: Box<Stmt>,
+    pub increment: Option<Expr>,
 }
 
 pub trait StmtVisitor<T> {
     fn visit_block_stmt(&mut self, stmt: &BlockStmt) -> Result<T, LoxResult>;
+    fn visit_break_stmt(&mut self, stmt: &BreakStmt) -> Result<T, LoxResult>;
+    fn visit_class_stmt(&mut self, stmt: &ClassStmt) -> Result<T, LoxResult>;
+    fn visit_const_stmt(&mut self, stmt: &ConstStmt) -> Result<T, LoxResult>;
+    fn visit_continue_stmt(&mut self, stmt: &ContinueStmt) -> Result<T, LoxResult>;
+    fn visit_destructure_stmt(&mut self, stmt: &DestructureStmt) -> Result<T, LoxResult>;
+    fn visit_enum_stmt(&mut self, stmt: &EnumStmt) -> Result<T, LoxResult>;
     fn visit_expression_stmt(&mut self, stmt: &ExpressionStmt) -> Result<T, LoxResult>;
+    fn visit_forin_stmt(&mut self, stmt: &ForInStmt) -> Result<T, LoxResult>;
     fn visit_function_stmt(&mut self, stmt: &FunctionStmt) -> Result<T, LoxResult>;
+    fn visit_import_stmt(&mut self, stmt: &ImportStmt) -> Result<T, LoxResult>;
     fn visit_if_stmt(&mut self, stmt: &IfStmt) -> Result<T, LoxResult>;
+    fn visit_match_stmt(&mut self, stmt: &MatchStmt) -> Result<T, LoxResult>;
     fn visit_print_stmt(&mut self, stmt: &PrintStmt) -> Result<T, LoxResult>;
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) -> Result<T, LoxResult>;
+    fn visit_throw_stmt(&mut self, stmt: &ThrowStmt) -> Result<T, LoxResult>;
+    fn visit_trait_stmt(&mut self, stmt: &TraitStmt) -> Result<T, LoxResult>;
+    fn visit_try_stmt(&mut self, stmt: &TryStmt) -> Result<T, LoxResult>;
     fn visit_var_stmt(&mut self, stmt: &VarStmt) -> Result<T, LoxResult>;
+    fn visit_yield_stmt(&mut self, stmt: &YieldStmt) -> Result<T, LoxResult>;
     fn visit_while_stmt(&mut self, stmt: &WhileStmt) -> Result<T, LoxResult>;
 }
 
@@ -90,36 +246,120 @@ impl BlockStmt {
     }
 }
 
+impl BreakStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_break_stmt(self)
+    }
+}
+
+impl ClassStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_class_stmt(self)
+    }
+}
+
+impl ConstStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_const_stmt(self)
+    }
+}
+
+impl ContinueStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_continue_stmt(self)
+    }
+}
+
+impl DestructureStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_destructure_stmt(self)
+    }
+}
+
+impl EnumStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_enum_stmt(self)
+    }
+}
+
 impl ExpressionStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_expression_stmt(self)
     }
 }
 
+impl ForInStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_forin_stmt(self)
+    }
+}
+
 impl FunctionStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_function_stmt(self)
     }
 }
 
+impl ImportStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_import_stmt(self)
+    }
+}
+
 impl IfStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_if_stmt(self)
     }
 }
 
+impl MatchStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_match_stmt(self)
+    }
+}
+
 impl PrintStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_print_stmt(self)
     }
 }
 
+impl ReturnStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_return_stmt(self)
+    }
+}
+
+impl ThrowStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_throw_stmt(self)
+    }
+}
+
+impl TraitStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_trait_stmt(self)
+    }
+}
+
+impl TryStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_try_stmt(self)
+    }
+}
+
 impl VarStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_var_stmt(self)
     }
 }
 
+impl YieldStmt {
+    pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
+        visitor.visit_yield_stmt(self)
+    }
+}
+
 impl WhileStmt {
     pub fn accept<T>(&self, visitor: &mut dyn StmtVisitor<T>) -> Result<T, LoxResult> {
         visitor.visit_while_stmt(self)
@@ -4,39 +4,73 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
+    Question,
+    QuestionQuestion,
+    QuestionDot,
     Dot,
+    DotDot,
+    DotDotDot,
     Minus,
+    MinusEqual,
+    MinusMinus,
     Plus,
+    PlusEqual,
+    PlusPlus,
     Semicolon,
     Slash,
+    SlashEqual,
     Star,
+    StarEqual,
+    StarStar,
+    Percent,
+    Pipe,
+    TildeSlash,
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    EqualGreater,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
     Identifier,
     String,
+    /// The part of an interpolated string before a `${`
+    Interpolation,
     Number,
     And,
+    Break,
+    Catch,
     Class,
+    Const,
+    Continue,
     Else,
+    Enum,
     False,
+    Finally,
     Fun,
     For,
     If,
+    Import,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
+    Trait,
     True,
+    Try,
     Var,
     While,
+    Yield,
     Eof,
 }